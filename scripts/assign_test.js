@@ -0,0 +1,18 @@
+// Chained assignments, including setters and frozen objects, which matter most
+// with `--split-assignments`.
+var log = [];
+function L(x, v) { log.push(x); return v; }
+var a, b, c = 3, v, r, k = {}, z = {};
+a = b = c;
+v = (r = function () { return 1; }, function () { return 2; });
+k.D = (k.Rs = function () { return "rs"; });
+var s = { set p(x) { log.push("set " + x); }, get p() { return "got"; } };
+var q = s.p = 5;
+a = s.p = b = (L("m"), 7);
+(L("o"), k).x = (L("i"), z).y = L("val", 9);
+var n = 1; a = n += 2;
+k.c = 1; a = k.c += 4;
+a = [b, c] = [10, 11];
+var fr = Object.freeze({f: 1}); a = fr.f = 2;
+L(JSON.stringify([a, b, c, k.D === k.Rs, v(), r(), q, z.y, k.x, n, k.c, fr.f]));
+console.log(JSON.stringify(log));
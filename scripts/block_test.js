@@ -0,0 +1,18 @@
+// Blocks that have to stay for their scope, and ones that can be flattened.
+var log = [];
+function L(x, v) { log.push(x); return v; }
+var x = "outer";
+{ let x = (L("a"), 1); L(x); }
+{ let x = (L("b"), 2); L(x); }
+{ const y = 3; L(y); }
+{ class C { m() { return "c"; } } L(new C().m()); }
+{ var v = (L("v"), 4); }
+L([x, v]);
+var fns = [];
+for (var i = 0; i < 2; i++) {
+    { let j = (L("j"), i); fns.push(() => j); }
+}
+L(fns.map(f => f()));
+if (x) { { let x = (L("nested"), 5); L(x); } }
+label: { let z = (L("z"), 6); if (z) break label; L("skipped"); }
+console.log(JSON.stringify(log));
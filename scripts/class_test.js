@@ -0,0 +1,32 @@
+// Classes whose superclass and computed keys hoist lines. They're evaluated
+// in source order, so an earlier key is kept in a temp when a later one's
+// lines run first.
+var log = [];
+function L(x, v) { log.push(x); return v; }
+function f(x) { L(x); return x; }
+class A {
+    [f("a")]() { return "a"; }
+    [(f("b"), "c")]() { return "c"; }
+}
+L(Object.getOwnPropertyNames(A.prototype));
+var k = "first";
+class B extends (L("super"), A) {
+    [k]() { return (L("m"), 1); }
+    [(k = "second", L("key"), k)]() { return 2; }
+    static [(L("static"), "s")]() { return 3; }
+    get [(L("getter"), "g")]() { return 4; }
+}
+L(Object.getOwnPropertyNames(B.prototype));
+L([new B().first(), new B().second(), B.s(), new B().g, new B().a()]);
+var C = class extends (L("expr super"), B) {
+    [(L("expr key"), "n")]() { return super.first(); }
+};
+L(new C().n());
+// Each computed key is converted before the next one runs.
+var conv = { toString() { L("toString"); return "conv"; } };
+class D extends C {
+    [conv]() { return super.n(); }
+    [(L("after conv"), "k")]() { return super.first((L("arg"), 0)); }
+}
+L([new D().conv(), new D().k()]);
+console.log(JSON.stringify(log));
@@ -0,0 +1,45 @@
+// Destructuring whose defaults, keys and targets hoist lines. Each property
+// is read, defaulted and bound before the next one, so later targets,
+// getters and bindings must not run ahead of an earlier default.
+var log = [];
+function L(x, v) { log.push(x); return v; }
+var o = {}, c, e;
+({c = (L("c"), o.x), d: o.x} = {d: 4});
+L([c, o.x]);
+var s = { set y(v) { L("set y " + v); } };
+[e = (L("e"), 1), s.y] = [undefined, 2];
+L(e);
+var [p = (L("p"), q), q] = [undefined, 2];
+L([p, q]);
+function* gen() {
+    try { L("next"); yield undefined; L("not reached"); } finally { L("return"); }
+}
+var [g = (L("g"), 1)] = gen();
+L(g);
+var src = {
+    get a() { L("get a"); return undefined; },
+    get b() { L("get b"); return 2; },
+};
+var { a: a1 = (L("a1"), 1), b: b1, ...others } = src;
+L([a1, b1, others]);
+let { a: a2 = (L("a2"), 1), b: b2 } = src;
+L([a2, b2]);
+const { a: a3 = (L("a3"), 3), ["b"]: b3 = (L("b3"), 3), [(L("key"), "c")]: c3 = 4 } = src;
+L([a3, b3, c3]);
+var k = "b";
+({ [k]: o.z, a: o.w = (k = "a", L("w"), 5), [k]: o.v } = src);
+L([o.z, o.w, o.v]);
+var t = { n: { m: undefined } };
+var { n: { m = (L("m"), 6) }, n: nn } = t;
+L([m, nn]);
+try {
+    var { [(L("key before null"), "x")]: x } = null;
+} catch (err) {
+    L(err.constructor.name);
+}
+var target = o;
+({ a: target.u = (target = {}, L("u"), 7), b: target.r } = src);
+L([o.u, target.r]);
+var r = ({ a: c = (L("value"), 8) } = src);
+L([c, r === src]);
+console.log(JSON.stringify(log));
@@ -0,0 +1,21 @@
+// Short-circuits and conditionals whose value is thrown away become `if`s
+// with no temp, including chains, nested ones and ones in sequences.
+var log = [];
+function L(x, v) { log.push(x); return v; }
+var a = 1, b = 0, o = { n: 0 };
+a || L("or skipped");
+b || L("or run");
+a && b && L("and skipped");
+a && L("and run", 1) && L("chained");
+a && (b || L("nested"));
+b ? L("then skipped") : L("else run");
+a ? (L("then run"), o.n++) : L("else skipped");
+a ? b || L("arm") : 0;
+(a && L("first"), b || L("second"), L("last"));
+for (var i = 0; i < 3; i++) i % 2 || L("even " + i);
+function f(p) { p && p.n || L("f " + p); return p; }
+f(null); f(o);
+var arrow = x => void (x && L("arrow " + x));
+arrow(0); arrow(2);
+L(o.n);
+console.log(JSON.stringify(log));
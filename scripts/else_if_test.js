@@ -0,0 +1,20 @@
+// `else if` chains, with and without lines hoisted out of the nested tests.
+var log = [];
+function L(x, v) { log.push(x); return v; }
+function pick(n) {
+    var s = "", k = n;
+    if (n == 0) s = "zero";
+    else if (n == 1) s = "one";
+    else if ((L("check two"), n == 2)) s = "two";
+    else if (k = n * 2, k > 7) s = "big";
+    else if (!k || typeof k == "string") s = "odd";
+    else s = "other";
+    if (n == 0) { L("a"); } else if ([k, n][0] === 6) { L("six"); k = 0; } else if (L("last"), k) { L("k"); }
+    return s + k;
+}
+L([0, 1, 2, 3, 4].map(pick));
+var o = { n: 0, get g() { return ++this.n; } };
+if (o.n) L("no"); else if (o.g == 1) L("getter once"); else L("twice");
+var v = o.n > 5 ? "a" : o.n > 0 ? (L("mid"), "b") : "c";
+L(v);
+console.log(JSON.stringify(log));
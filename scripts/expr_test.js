@@ -0,0 +1,26 @@
+// new, templates, tagged templates, spread, update, await and yield with
+// hoisted operands.
+var log = [];
+function L(x, v) { log.push(x); return v; }
+function B(a, b) { this.s = [a, b]; }
+var o = { B, n: 0, tag(s, ...v) { return [this === o, s.join("|"), v]; } };
+var a = 1, arr = [1, 2];
+L(new (L("c"), o.B)((L("a1"), 1), (L("a2"), 2)).s);
+L(`x${(L("t1"), a)}y${(L("t2"), a = 2)}z`);
+// Each substitution is converted to a string before the next one runs.
+var str = { toString() { L("toString"); return "str"; } };
+L(`${str}-${(L("second"), 1)}-${a}`);
+L((L("tag"), o).tag`p${(L("q1"), 3)}q${(L("q2"), 4)}`);
+L(o.tag`only`[0]);
+function* gen() { L("g1"); yield 1; L("g2"); yield 2; }
+L([...gen(), (L("after"), 3)]);
+L(Math.max(...(L("s"), arr), (L("m"), 0)));
+var k = "n";
+o[(L("uk"), k)]++;
+L(++o[(L("uk2"), k)] + (L("r"), 1));
+function* g2() { var x = yield (L("y1"), 1); var z = (L("y2"), yield* [5, 6]); return [x, z]; }
+var it = g2(); L(JSON.stringify([it.next(), it.next(7), it.next(), it.next()]));
+var w = 0;
+async function h() { w = 1; var r = w + (await (L("aw"), 1)); var q = [w, (await 0, w)]; return [r, q]; }
+var p = h(); w = 5;
+p.then(r => console.log(JSON.stringify([log, r])));
@@ -0,0 +1,17 @@
+// for-in, for-of and for-await loops whose targets hoist lines.
+var log = [];
+function L(x, v) { log.push(x); return v; }
+var o = {}, a, b, r, fns = [];
+for (const [x, y = (L("dy"), 5)] of (L("rhs"), [[1], [2, 3]])) fns.push(() => x + y);
+for ([a, b = (L("db"), 7), ...r] of [[1], [2, 3, 4]]) L([a, b, r].join());
+for ({a, b: o[L("k"), "q"] = (L("dq"), 9), ...r} of [{a: 1, z: 2}]) L(JSON.stringify([a, o, r]));
+for (o[(L("key"), "m")] in (L("in"), {p: 1, q: 2})) L(o.m);
+for (let [k] in {xy: 1}) fns.push(() => k);
+for (var {length} of ["abc"]) L(length);
+// Each default runs before the targets after it are assigned.
+var s = { set y(v) { L("set y " + v); } };
+for ([a = (L("da " + b), b), b] of [[undefined, 1], [2, 3]]) L([a, b]);
+for ({a = (L("getter first"), s.z), y: s.y} of [{ get y() { L("get y"); return 4; } }]) L(a);
+for (var {c = (L("dc " + d), d), d} of [{d: 5}, {c: 0, d: 6}]) L([c, d]);
+async function f() { for await (const [v = (L("dv"), 0)] of [Promise.resolve([]), [1]]) L("v" + v); }
+f().then(() => console.log(JSON.stringify([log, fns.map(g => g())])));
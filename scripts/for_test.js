@@ -0,0 +1,13 @@
+// For-loop heads with side effects, including `let` bindings captured by
+// closures.
+var log = [];
+function L(x, v) { log.push(x); return v; }
+var U, Z = 0, fns = [];
+for (U = []; 5 > Z; Z++, L("x" + Z)) { if (Z == 2) continue; U.push(Z); }
+for (var i = 0; i < 3; i++) L("i" + i);
+for (let j = (L("j0"), 0); (L("t" + j), j < 3); j++) { fns.push(() => j); if (j == 1) continue; }
+for (let k = 0, m = (L("m", k), 1); k < 2; k += (L("upd"), 1)) { fns.push(() => k + m); }
+for (const c = {n: 0}; c.n < 2; L("cu"), c.n++) { L("c" + c.n); }
+for (L("init"), i = 0; ; ) { if (i++ > 2) break; }
+outer: for (var p = 0; p < 3; p++, L("pu")) { for (;;) { continue outer; } }
+console.log(JSON.stringify([log, U, Z, fns.map(f => f()), i, p]));
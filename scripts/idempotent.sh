@@ -0,0 +1,22 @@
+#!/bin/sh
+# Checks that deblockifying is a no-op on its own output, for every script in
+# this directory. Options are passed on to deblockify, e.g.
+# `scripts/idempotent.sh --temps=var`.
+cd "$(dirname "$0")/.." || exit 1
+cargo build -q --bin deblockify || exit 1
+bin="$PWD/target/debug/deblockify"
+work=$(mktemp -d)
+status=0
+for script in scripts/*.js; do
+    (cd "$work" && "$bin" "$OLDPWD/$script" "$@" >/dev/null && mv out.js once.js &&
+        "$bin" once.js "$@" >/dev/null) || { echo "FAIL $script"; status=1; continue; }
+    if cmp -s "$work/once.js" "$work/out.js"; then
+        echo "ok   $script"
+    else
+        echo "DIFF $script"
+        diff "$work/once.js" "$work/out.js" | head -20
+        status=1
+    fi
+done
+rm -rf "$work"
+exit $status
@@ -0,0 +1,27 @@
+// Temps that get inlined or dropped after blockifying, and ones that must
+// stay.
+var log = [];
+function L(x, v) { log.push(x); return v; }
+var o = { v: 1, get g() { L("get"); return this.v; } };
+var a = 0, b = 2, c = 3, i;
+L((L("s1"), a) || L("or"));
+(L("s2"), a) || L("guard");
+a && L("and");
+var x = a ? (L("t"), b) : c > 2 ? (L("u"), c) : (L("v"), 4);
+var y = (a ? b : c) + o.g;
+function f(n) { return n > 1 ? n : n ? (L("one"), 1) : 0; }
+L(f(0) + f(1) + f(5));
+var z = a || (b && c) || 9;
+for (i = 0; i < 3; i++) {
+    var w = (L("w"), i) || (L("zero"), 10);
+    var q = w && (i > 1 ? w : (L("small"), -w));
+    L(q);
+}
+var fns = [];
+for (i = 0; i < 2; i++) {
+    fns.push(() => (L("closure"), i) || b);
+}
+var n = (o.v = 5, o.g) && (a = 7, a);
+var m = o.v++ ? o.v : (L("no"), 0);
+L([x, y, z, n, m, a, fns.map(g => g())]);
+console.log(JSON.stringify(log));
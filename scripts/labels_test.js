@@ -0,0 +1,55 @@
+// Labeled loops, blocks and switches whose tests and updates hoist lines, so
+// `break label` and `continue label` have to land on the lowered statement.
+var log = [];
+function L(x, v) { log.push(x); return v; }
+var t = 0;
+outer: while ((L("tick " + t), t++ < 4)) {
+    var u = 0;
+    inner: do {
+        if ((L("check"), t === 2)) continue outer;
+        if (t === 4) break outer;
+        L("step " + t + " " + u);
+    } while ((L("advance"), ++u < 2));
+    L("after inner " + t);
+}
+L("t " + t);
+var n = 3, m = 3;
+rows: for (var i = 0; i < n; i++) {
+    cols: for (var j = (L("reset"), 0); j < m; j++) {
+        if ((i === 1 && j === 1) || (L("mark " + i + j), false)) continue rows;
+        if (j > i) break cols;
+        L("cell " + i + j);
+    }
+    L("row " + i);
+}
+var w = 0;
+a: b: while ((L("poll"), w++ < 5)) {
+    if (w === 2) continue b;
+    if (w === 3) break a;
+    L("waiting " + w);
+}
+L("w " + w);
+var table = { x: 1, y: 2, z: 3 };
+function find(target) {
+    found: {
+        for (var k in (L("load"), table)) {
+            if (table[k] === target) break found;
+        }
+        L("missing " + target);
+        return null;
+    }
+    return k;
+}
+L([find(2), find(4)]);
+function choose(choice) {
+    pick: switch ((L("choose"), choice)) {
+        case (L("first"), 1):
+            if (choice) break pick;
+            L("not reached");
+        default:
+            L("fallback");
+    }
+    return choice;
+}
+L([choose(1), choose(2)]);
+console.log(JSON.stringify(log));
@@ -0,0 +1,19 @@
+// Constructs that lower levels leave as expressions, with functions and
+// sequences inside them, for `--level=sequences`, `--level=control` and
+// toggles like `--no-objects`.
+var log = [];
+function L(x, v) { log.push(x); return v; }
+var o = { a: (L("a"), 1), [(L("key"), "b")]: 2, ...(L("spread"), { c: 3 }), f() { return (L("f"), 4); } };
+var p = o.a && (L("and"), function () { return (L("inner"), o.b); });
+var q = o.z ? (L("no"), 0) : (L("yes"), () => (L("arrow"), o.c));
+L([p(), q(), o.f()]);
+var t = { n: 1 };
+t.n += (L("plus"), 2);
+t[(L("index"), "m")] = (L("value"), 5);
+var { n, m = (L("default"), 6), k = (L("k"), 7) } = t;
+var r = 0;
+r = (L("r"), t.n) || (L("unused"), 0);
+o.a || (L("or"), o.a = 9);
+L([t, n, m, k, r, o.a]);
+for (var i = 0, j = (L("j"), 2); i < j; i++, L("step")) L(i);
+console.log(JSON.stringify(log));
@@ -0,0 +1,18 @@
+// Short-circuits and conditionals that stay inline because their right side
+// only reads, and ones that have to become `if`s.
+var log = [];
+function L(x, v) { log.push(x); return v; }
+var o = { a: 1, b: 0, get g() { L("g"); return 2; } };
+var x = o.a && o.b || !o.c && o.g;
+var y = (o.a ? o.b : o.c) ? 1 : o.b ? 2 : 3;
+var z = (o.a || o.b) && (o.b ? o.g : o.a) + 1;
+var w = o.b && L("not called") || L("called", 4);
+var v = o.a ? (o.b = 5) : 6;
+var u = (L("test"), o.b) ? o.a : (L("else"), o.g);
+var t = !(o.a && o.b) ? -(o.b || 1) : typeof (o.c && o.d);
+var s = o.missing && o.missing.deep;
+var r = (0, o.a) ? o : null;
+L([x, y, z, w, v, u, t, s, r && r.a]);
+function f(p, q) { return p && p.n > 0 ? p.n * 2 : q || -1; }
+L([f({ n: 3 }), f(null, 7), f()]);
+console.log(JSON.stringify(log));
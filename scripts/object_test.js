@@ -0,0 +1,44 @@
+// Object literals whose keys and values hoist lines. The literal stays whole
+// and earlier keys and values go into temps when later lines could change
+// them, including shorthands, spreads, accessors and `__proto__`.
+var log = [];
+function L(x, v) { log.push(x); return v; }
+var a = 1, k = "key";
+var o = {
+    a,
+    [k]: (L("value"), k),
+    [(k = "other", L("computed"), k)]: 2,
+    b: (a = 2, L("b"), a),
+};
+L([o, a]);
+var spread = { s: 1, t: 2 };
+var p = {
+    ...spread,
+    s: (spread.s = 5, L("after spread"), spread.s),
+    ...(L("second spread"), spread),
+    u: (spread.t = 9, 3),
+};
+L([p, spread]);
+var hits = 0;
+var q = {
+    n: (L("n"), 1),
+    get g() { hits++; return this.n; },
+    set g(v) { L("set " + v); this.n = v; },
+    [(L("getter key"), "h")]: 0,
+    get [(L("computed getter"), "c")]() { return "c"; },
+};
+q.g = 4;
+L([q.g, q.c, q.h, hits]);
+var __proto__ = { inherited: true };
+var own = { __proto__, x: (__proto__ = null, L("reassigned"), 1) };
+L([Object.keys(own), own.inherited, Object.getPrototypeOf(own) === Object.prototype]);
+var proto = { inherited: true };
+var set = { __proto__: (L("proto"), proto), y: (proto = null, 2) };
+L([Object.keys(set), set.inherited]);
+function make(n) { return { n, [n]: (n += 1, L("make"), n), n2: n }; }
+L(make(1));
+// A computed key is converted before the values after it run.
+var key = { toString() { L("toString"); return "conv"; } };
+L({[key]: (L("val"), 1), after: (L("after"), 2)});
+L({first: (L("first"), 0), [key]: 1, [(L("next key"), "n")]: (L("next"), 2)});
+console.log(JSON.stringify(log));
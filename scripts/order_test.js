@@ -0,0 +1,49 @@
+// Evaluation-order samples, shaped after bg.js and ex.js.
+var log = [];
+function L(name, value) {
+  log.push(name);
+  return value;
+}
+
+var F = [1, 2, 3, 4, 5];
+var M = 0;
+var Y;
+var vm = {
+  regs: {},
+  T: function (k) {
+    L("T" + k);
+    return this.regs[k] || (this.regs[k] = []);
+  },
+};
+var u = function (self, op, arg) {
+  L("u" + op + ":" + arg);
+};
+
+(function () {
+  (((Y = M, this).T(215).length = F.shift(), u)(this, 17, F.shift()), this.T(189).length = F.shift(), this.T(30)).length = F.shift();
+}).call(vm);
+
+var a = 1;
+var sum = L("left", a) + (a = 10, L("right", a));
+var arr = [a, (a = 20, L("second", a)), a];
+var called = L("callee", function (x, y) { return x + y; })(a, (a = 30, a));
+var obj = {x: 1};
+var key = "x";
+var read = obj[key] + (key = "y", obj = {y: 2}, obj[key]);
+var cond = L("test", a) ? (L("then"), a) : (L("else"), 0);
+
+// A call can change a variable from inside a closure, so it can't be read
+// before the call's lines. A local nothing captures can.
+var C = 1;
+function step() { C = 5; return 2; }
+var closed = C + (step(), 3);
+C = 1;
+var passed = u === u && [C, (step(), 7)];
+function local(n) {
+  var k = n;
+  C = 1;
+  return [k + (step(), 1), n * (step(), 1), C + (L("local"), 0)];
+}
+var locals = local(4);
+
+console.log(JSON.stringify([log, vm.regs, Y, sum, arr, called, read, cond, closed, passed, locals]));
@@ -0,0 +1,43 @@
+// Parameter defaults and patterns that hoist lines, and ones that have to
+// stay put.
+var log = [];
+function L(x, v) { log.push(x); return v; }
+function f(a, b = (L("b"), 2), {c, d = (L("d"), 4)} = (L("obj"), {c: 3}), ...[e = (L("e"), 5)]) {
+    return [a, b, c, d, e, arguments.length];
+}
+L(f(1));
+L(f(1, 0, {c: 0, d: 0}, 0));
+L([f.length, ((a, b = (L("x"), 1)) => a).length]);
+var g = (x, y = (L("y"), x + 1), [z] = (L("z"), [y * 2])) => [x, y, z];
+L(g(1));
+L(g(1, undefined, [7]));
+class C {
+    constructor(n = (L("ctor"), 1)) { this.n = n; }
+    m({k} = (L("m"), {k: this.n}), [j] = [k]) { return k + j; }
+}
+L(new C().m());
+var o = { get p() { L("p"); return 1; } };
+function later(q = (L("q"), 1), {p} = o, r = p) { return [q, p, r]; }
+L(later());
+// The default would read a parameter before it's bound.
+function tdz(s = (L("s"), t), t) { return s; }
+try { tdz(); } catch (e) { L(e.name); }
+// The default can't see the body's `u`.
+var u = "outer";
+function shadowed(v = (L("v"), u)) { var u = "inner"; return v; }
+L(shadowed());
+// Destructured parameters run each default before reading the properties
+// and elements after it.
+function ordered([p = (L("p"), q), q]) { return [p, q]; }
+try { ordered([undefined, 2]); } catch (e) { L(e.name); }
+L(ordered([1, 2]));
+var arg = { get a() { L("get a"); }, get b() { L("get b"); return { c: 3 }; } };
+function props({a = (L("a"), 1), b: {c} = (L("b"), {c: 0})}, d = (L("d"), 4)) { return [a, c, d]; }
+L(props(arg));
+function* gen(w = (L("w"), 1)) { yield w; }
+var it = gen();
+L("made");
+L(it.next().value);
+function sloppy(a, b = (L("sloppy"), 2)) { arguments[0] = 9; return a + b; }
+L(sloppy(1));
+console.log(JSON.stringify(log));
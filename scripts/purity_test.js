@@ -0,0 +1,27 @@
+// Leftover values that must stay because they can throw or run code, and ones
+// that can go.
+var log = [];
+function L(x, v) { log.push(x); return v; }
+var o = { get g() { L("getter"); return 1; }, valueOf() { L("valueOf"); return 2; } };
+var n = null, a = 1;
+try { (L("missing"), notDeclared, L("not reached")); } catch (e) { L(e.name); }
+try { (L("null"), n.x, L("not reached")); } catch (e) { L(e.name); }
+(L("get"), o.g, L("after get"));
+(L("convert"), o + 1, -o, `${o}`, L("after convert"));
+(L("pure"), 0, "s", a, this, typeof missingToo, void a, !a, function () {}, L("after pure"));
+(L("in"), "g" in o, o instanceof Object);
+a ? o.g : 0;
+a || o.g;
+var t = (L("keep"), o.g, a);
+L(typeof t);
+// Only the function declares `local`, and `later` isn't initialized yet, even
+// for `typeof`, so these read nothing.
+function declares() { var local = 1; return local; }
+try { (L("local"), local, L("not reached")); } catch (e) { L(e.name); }
+try { (L("tdz"), later, L("not reached")); } catch (e) { L(e.name); }
+try { (L("typeof tdz"), typeof later, L("not reached")); } catch (e) { L(e.name); }
+let later = declares();
+(L("initialized"), later, typeof later, L("after initialized"));
+function early() { return later; }
+(L("from a function"), early(), L("after function"));
+console.log(JSON.stringify(log));
@@ -0,0 +1,31 @@
+// Super calls and super member calls whose arguments hoist lines. `super`
+// stays in place, since it can't be kept in a temp, and only the other
+// operands are spilled.
+var log = [];
+function L(x, v) { log.push(x); return v; }
+class A {
+    constructor(n) { this.n = n; }
+    m(x) { return [this.n, x]; }
+    get g() { return this.n; }
+}
+var n = 1;
+class B extends A {
+    constructor() {
+        super((L("constructor"), n), (n = 2, L("second"), n));
+    }
+    m(x) { return super.m((L("m"), x)); }
+    k(x) { return super[(L("key"), "m")]((L("argument"), x), (x = 5, x)); }
+    both() { return [super.g, (L("after g"), this.n)]; }
+    arrow() { return ((y) => super.m((L("arrow"), y)))(4); }
+    set(v) { super.s = (L("set"), v); return this.s; }
+}
+var b = new B();
+L([b.n, b.m(3), b.k(1), b.both(), b.arrow(), b.set(6)]);
+var base = { x(a) { return [this === o, a]; } };
+var o = {
+    __proto__: base,
+    m() { return super.x((L("object"), 1)); },
+    [(L("computed"), "c")]() { return super.x((L("computed method"), 2)); },
+};
+L([o.m(), o.c()]);
+console.log(JSON.stringify(log));
@@ -0,0 +1,57 @@
+// Switches whose case tests hoist lines, which pick the case with an if/else
+// chain first and then switch on its index. Only the tests up to the match
+// run, `default` can sit in the middle, and cases fall through.
+var log = [];
+function L(x, v) { log.push(x); return v; }
+function pick(n) {
+    var out = [];
+    switch ((L("subject"), n)) {
+        case (L("one"), 1):
+            out.push("one");
+        case (L("two"), 2):
+            out.push("two");
+            break;
+        default:
+            out.push("default");
+        case (L("three"), 3):
+            out.push("three");
+        case 4:
+            out.push("four");
+            break;
+        case (L("five"), 5):
+            out.push("five");
+    }
+    return out;
+}
+L(pick(1));
+L(pick(2));
+L(pick(3));
+L(pick(4));
+L(pick(5));
+L(pick(6));
+// Only a default, and a default at the end that falls out of the switch.
+function last(n) {
+    switch (n) {
+        case (L("a"), "a"):
+            return "a";
+        default:
+            L("default");
+    }
+    return "after";
+}
+L([last("a"), last("b")]);
+// The subject is evaluated once, before any test.
+var i = 0;
+switch (i++) {
+    case (L(i), 0):
+        L("matched " + i);
+}
+// Tests compare strictly.
+switch ("1") {
+    case (L("number"), 1):
+        L("loose");
+        break;
+    case (L("string"), "1"):
+        L("strict");
+}
+console.log(JSON.stringify(log));
@@ -0,0 +1,17 @@
+// Conditionals with hoisted lines in the test or an arm, which become an
+// `if`/`else` assigning a temp, nested and in the operands of other things.
+var log = [];
+function L(x, v) { log.push(x); return v; }
+var a = 1, b = 0;
+var x = (L("test"), a) ? (L("then"), 2) : (L("else"), 3);
+var y = b ? (L("not run"), 4) : (L("run"), 5);
+var z = a ? b ? (L("inner then"), 6) : (L("inner else"), 7) : (L("outer else"), 8);
+var w = (L("left"), 10) + (a ? (L("arm"), 1) : 0) + (L("right"), 100);
+L([x, y, z, w]);
+function f(n) { return n > 0 ? (L("pos"), n) : n < 0 ? (L("neg"), -n) : (L("zero"), 0); }
+L([f(2), f(-3), f(0)]);
+var o = { n: 1 };
+o[a ? (L("key"), "m") : "n"] = (L("value"), b ? 1 : (L("v"), 2));
+L(o);
+for (var i = 0; i < 3; i++) L(i % 2 ? (L("odd"), i) : i);
+console.log(JSON.stringify(log));
@@ -0,0 +1,21 @@
+// Calls through a sequence like `(0, o.m)(x)` don't pass `o` as `this`, and
+// an indirect `eval` runs in the global scope, so unwrapping the sequence
+// has to keep them that way.
+var log = [];
+function L(x, v) { log.push(x); return v; }
+var o = {
+    m(x) { "use strict"; return [this === o, this === undefined, x]; },
+    tag(s) { "use strict"; return [this === o, s[0]]; },
+};
+L((0, o.m)(1));
+L((L("first"), o.m)(2));
+L((L("object"), o)[(L("key"), "m")](3));
+L((L("computed"), o[(L("key"), "m")])(4));
+L((L("tag"), o.tag)`t`);
+L(o.m((L("bound"), 5)));
+function scoped() {
+    var v = "local";
+    return [eval("typeof v"), (0, eval)("typeof v"), (L("indirect"), eval)("typeof v")];
+}
+L(scoped());
+console.log(JSON.stringify(log));
@@ -0,0 +1,24 @@
+// While and do-while loops whose tests hoist lines, which have to run on
+// every iteration, including after `continue` and across labels.
+var log = [];
+function L(x, v) { log.push(x); return v; }
+var i = 0;
+while ((L("w" + i), i < 3)) { i++; if (i == 2) continue; L("body " + i); }
+var j = 0;
+do { j++; if (j == 2) continue; L("do " + j); } while ((L("d" + j), j < 4));
+var k = 5;
+do L("once " + k); while ((L("test"), k < 0));
+var n = 0, m;
+outer: while ((L("outer " + n), n < 2)) {
+    n++;
+    m = 0;
+    do { m++; if (m == 1) continue outer; } while ((L("inner"), m < 3));
+}
+var fns = [];
+var p = 0;
+while ((L("p"), p++ < 2)) { let q = p; fns.push(() => q); }
+var it = [1, 2];
+while (it.length && (L("pop"), it.pop())) (L("seq body"), it.length);
+function f(x) { while ((x = x - 1, L("f" + x), x)) { if (x == 1) return "ret"; } return "end"; }
+L([f(3), f(1), fns.map(g => g())]);
+console.log(JSON.stringify(log));
@@ -0,0 +1,3592 @@
+use ressa::Parser;
+
+use resw::Writer;
+
+use std::borrow::Cow;
+use std::collections::HashSet;
+
+use resast::expr::Expr;
+use resast::prelude::*;
+use std::iter;
+
+use std::fmt::{self, Debug, Display};
+
+use crate::inline::inline_temps;
+use crate::names;
+use crate::naming::Namer;
+use crate::purity::{Assumptions, Purity};
+use crate::visit::{
+    walk_expr, walk_func, walk_params, walk_part, walk_program, walk_prop, walk_stmt, Bindings,
+    Declared, Names, Suspends, Visit, WritesTo,
+};
+use crate::Blockable;
+
+#[derive(Debug)]
+enum Line<'a> {
+    Part(ProgramPart<'a>),
+    Variable(Cow<'a, str>, Option<Expr<'a>>),
+}
+
+fn get_id<'a>(name: &str) -> Expr<'a> {
+    Expr::Ident(get_id_pat(name))
+}
+
+fn let_line<'a>(name: &str, init: Option<Expr<'a>>) -> Line<'a> {
+    Line::Part(ProgramPart::Decl(Decl::Var(
+        VarKind::Let,
+        vec![VarDecl {
+            id: Pat::Ident(get_id_pat(name)),
+            init,
+        }],
+    )))
+}
+
+fn get_id_pat<'a>(name: &str) -> Ident<'a> {
+    Ident {
+        name: Cow::Owned(name.to_string()),
+    }
+}
+
+fn void_0<'a>() -> Expr<'a> {
+    Expr::Unary(UnaryExpr {
+        operator: UnaryOp::Void,
+        prefix: true,
+        argument: Box::new(Expr::Lit(Lit::Number("0".into()))),
+    })
+}
+
+// `if (name === void 0) { ...; name = value; }`, giving `name` the default
+// that `block` works out.
+fn default_line<'a>(name: &str, mut block: Block<'a>) -> Line<'a> {
+    let default = assign_line(name, block.expr());
+    let mut lines = block.lines();
+    lines.push(Line::Part(default));
+    Line::Part(ProgramPart::Stmt(Stmt::If(IfStmt {
+        test: Expr::Binary(BinaryExpr {
+            operator: BinaryOp::StrictEqual,
+            left: Box::new(get_id(name)),
+            right: Box::new(void_0()),
+        }),
+        consequent: wrap(Block::from_lines(lines)),
+        alternate: None,
+    })))
+}
+
+// `target = value`, or a declaration of `target` when `binding` is set.
+fn bind_line<'a>(target: Pat<'a>, value: Expr<'a>, binding: Option<VarKind>) -> Line<'a> {
+    Line::Part(match binding {
+        Some(kind) => ProgramPart::Decl(Decl::Var(
+            kind,
+            vec![VarDecl {
+                id: target,
+                init: Some(value),
+            }],
+        )),
+        None => ProgramPart::Stmt(Stmt::Expr(Expr::Assign(AssignExpr {
+            operator: AssignOp::Equal,
+            left: match target {
+                Pat::Ident(ident) => AssignLeft::Expr(Box::new(Expr::Ident(ident))),
+                target => AssignLeft::Pat(target),
+            },
+            right: Box::new(value),
+        }))),
+    })
+}
+
+// The parameters and top-level declarations of a function that only its own
+// code can change, because no nested function mentions them. Parameters can
+// also change through `arguments`, and anything through `eval` or `with`.
+fn uncaptured<'a>(
+    params: &[FuncArg<'a>],
+    parts: &[ProgramPart<'a>],
+    body: Option<&Expr<'a>>,
+) -> HashSet<String> {
+    #[derive(Default)]
+    struct Refs {
+        // Names read or written by the function itself.
+        own: HashSet<String>,
+        // Names mentioned by the functions nested in it.
+        nested: HashSet<String>,
+        with: bool,
+    }
+    impl<'a> Visit<'a> for Refs {
+        fn visit_stmt(&mut self, stmt: &Stmt<'a>) {
+            self.with |= matches!(stmt, Stmt::With(_));
+            walk_stmt(self, stmt)
+        }
+        fn visit_expr(&mut self, expr: &Expr<'a>) {
+            match expr {
+                Expr::ArrowFunc(_) => {
+                    let mut names = Names::default();
+                    names.visit_expr(expr);
+                    self.nested.extend(names.names);
+                }
+                expr => walk_expr(self, expr),
+            }
+        }
+        fn visit_func(&mut self, func: &Func<'a>) {
+            let mut names = Names::default();
+            walk_func(&mut names, func);
+            self.nested.extend(names.names);
+        }
+        fn visit_ident(&mut self, ident: &Ident<'a>) {
+            self.own.insert(ident.name.to_string());
+        }
+    }
+
+    let mut refs = Refs::default();
+    walk_params(&mut refs, params);
+    for part in parts {
+        refs.visit_part(part);
+    }
+    if let Some(body) = body {
+        refs.visit_expr(body);
+    }
+    if refs.with || refs.own.contains("eval") || refs.nested.contains("eval") {
+        return HashSet::new();
+    }
+
+    let mut declared = Declared::body(parts);
+    if !refs.own.contains("arguments") {
+        declared.bind_params(params);
+    }
+    declared.names.retain(|name| !refs.nested.contains(name));
+    declared.names
+}
+
+// Whether `expr` reads a property anywhere, which `is_stable` can't vouch for.
+fn reads_property(expr: &Expr) -> bool {
+    struct Reads {
+        found: bool,
+    }
+    impl<'a> Visit<'a> for Reads {
+        fn visit_expr(&mut self, expr: &Expr<'a>) {
+            self.found |= matches!(expr, Expr::Member(_));
+            walk_expr(self, expr)
+        }
+    }
+    let mut reads = Reads { found: false };
+    reads.visit_expr(expr);
+    reads.found
+}
+
+// A parameter as a pattern, with defaults as `Pat::Assign`. Anything else
+// comes back as it was.
+fn param_pat(param: FuncArg) -> Result<Pat, FuncArg> {
+    match param {
+        FuncArg::Pat(pat) => Ok(pat),
+        FuncArg::Expr(Expr::Ident(ident)) => Ok(Pat::Ident(ident)),
+        FuncArg::Expr(Expr::Assign(AssignExpr {
+            operator: AssignOp::Equal,
+            left: AssignLeft::Pat(left),
+            right,
+        })) => Ok(Pat::Assign(AssignPat {
+            left: Box::new(left),
+            right,
+        })),
+        FuncArg::Expr(Expr::Assign(AssignExpr {
+            operator: AssignOp::Equal,
+            left: AssignLeft::Expr(left),
+            right,
+        })) => match *left {
+            Expr::Ident(ident) => Ok(Pat::Assign(AssignPat {
+                left: Box::new(Pat::Ident(ident)),
+                right,
+            })),
+            left => Err(FuncArg::Expr(Expr::Assign(AssignExpr {
+                operator: AssignOp::Equal,
+                left: AssignLeft::Expr(Box::new(left)),
+                right,
+            }))),
+        },
+        param => Err(param),
+    }
+}
+
+// Every name the defaults and computed keys of a parameter use.
+fn param_refs(pat: &Pat) -> HashSet<String> {
+    struct Refs {
+        names: Names,
+    }
+    impl<'a> Visit<'a> for Refs {
+        fn visit_expr(&mut self, expr: &Expr<'a>) {
+            self.names.visit_expr(expr)
+        }
+    }
+    let mut refs = Refs {
+        names: Names::default(),
+    };
+    refs.visit_pat(pat);
+    refs.names.names
+}
+
+// Rebuilds a member expression from the values of `Blockifier::member_parts`.
+fn member<'a>(mut values: Vec<Expr<'a>>, property: Option<Expr<'a>>, computed: bool) -> Expr<'a> {
+    let property = property.unwrap_or_else(|| values.pop().expect("Member has a property"));
+    let object = values.pop().expect("Member has an object");
+    Expr::Member(MemberExpr {
+        object: Box::new(object),
+        property: Box::new(property),
+        computed,
+    })
+}
+
+// How a callee split up by `Blockifier::callee` is put back together.
+enum Callee<'a> {
+    Method(Option<Expr<'a>>, bool),
+    Reference,
+    Value,
+}
+
+impl<'a> Callee<'a> {
+    fn rebuild(self, mut values: Vec<Expr<'a>>) -> Expr<'a> {
+        match self {
+            Callee::Method(property, computed) => member(values, property, computed),
+            Callee::Reference => values.pop().expect("Call has a callee"),
+            Callee::Value => unbind(values.pop().expect("Call has a callee")),
+        }
+    }
+}
+
+// Keeps a computed callee from turning into a method call or a direct eval
+// once it's unwrapped: `(0, obj.method)(x)` must not become `obj.method(x)`.
+fn unbind(callee: Expr) -> Expr {
+    if keeps_binding(&callee) {
+        Expr::Sequence(vec![Expr::Lit(Lit::Number("0".into())), callee])
+    } else {
+        callee
+    }
+}
+
+fn keeps_binding(callee: &Expr) -> bool {
+    match callee {
+        Expr::Member(_) => true,
+        Expr::Ident(Ident { name }) => name == "eval",
+        _ => false,
+    }
+}
+
+// Whether converting a computed key to a property key could run code, which
+// it can't for a primitive literal.
+fn converts(key: &Expr) -> bool {
+    !matches!(key, Expr::Lit(lit) if !matches!(lit, Lit::RegEx(_)))
+}
+
+// `${value}`, which converts `value` to a string.
+fn to_string(value: Expr) -> Expr {
+    Expr::Lit(Lit::Template(TemplateLit {
+        quasis: vec![
+            TemplateElement::from(false, "", "`${"),
+            TemplateElement::from(true, "", "}`"),
+        ],
+        expressions: vec![value],
+    }))
+}
+
+// Takes the value back out of a `to_string` that wasn't spilled.
+fn unwrap_string(expr: Expr) -> Expr {
+    match expr {
+        Expr::Lit(Lit::Template(TemplateLit {
+            quasis,
+            mut expressions,
+        })) if expressions.len() == 1 && quasis.iter().all(|quasi| quasi.cooked.is_empty()) => {
+            expressions.pop().expect("One substitution")
+        }
+        expr => expr,
+    }
+}
+
+// The key for a property that used to be written `{a}`. A plain
+// `__proto__: value` would set the prototype instead of an own property.
+fn own_key(key: Ident) -> (PropKey, bool) {
+    if key.name == "__proto__" {
+        let key = Expr::Lit(Lit::String(StringLit::Double(key.name)));
+        (PropKey::Expr(key), true)
+    } else {
+        (PropKey::Expr(Expr::Ident(key)), false)
+    }
+}
+
+// The member expression that a destructuring target assigns to, with or
+// without a default: `{a: o.x}` or `{a: o.x = 1}`.
+fn member_target<'e, 'a>(target: &'e Expr<'a>) -> Option<&'e Expr<'a>> {
+    match target {
+        Expr::Member(_) => Some(target),
+        Expr::Assign(AssignExpr {
+            operator: AssignOp::Equal,
+            left: AssignLeft::Expr(left),
+            ..
+        }) if matches!(**left, Expr::Member(_)) => Some(left),
+        _ => None,
+    }
+}
+
+fn binary_op(op: AssignOp) -> Option<BinaryOp> {
+    match op {
+        AssignOp::Equal => None,
+        AssignOp::PlusEqual => Some(BinaryOp::Plus),
+        AssignOp::MinusEqual => Some(BinaryOp::Minus),
+        AssignOp::TimesEqual => Some(BinaryOp::Times),
+        AssignOp::DivEqual => Some(BinaryOp::Over),
+        AssignOp::ModEqual => Some(BinaryOp::Mod),
+        AssignOp::LeftShiftEqual => Some(BinaryOp::LeftShift),
+        AssignOp::RightShiftEqual => Some(BinaryOp::RightShift),
+        AssignOp::UnsignedRightShiftEqual => Some(BinaryOp::UnsignedRightShift),
+        AssignOp::OrEqual => Some(BinaryOp::Or),
+        AssignOp::XOrEqual => Some(BinaryOp::XOr),
+        AssignOp::AndEqual => Some(BinaryOp::And),
+        AssignOp::PowerOfEqual => Some(BinaryOp::PowerOf),
+    }
+}
+
+fn assign_line<'a>(name: &str, value: Expr<'a>) -> ProgramPart<'a> {
+    ProgramPart::Stmt(Stmt::Expr(Expr::Assign(AssignExpr {
+        operator: AssignOp::Equal,
+        left: AssignLeft::Expr(Box::new(get_id(name))),
+        right: Box::new(value),
+    })))
+}
+
+fn negate(expr: Expr) -> Expr {
+    match expr {
+        // `!!x` and `x` have the same truthiness.
+        Expr::Unary(UnaryExpr {
+            operator: UnaryOp::Not,
+            argument,
+            ..
+        }) => *argument,
+        expr => Expr::Unary(UnaryExpr {
+            operator: UnaryOp::Not,
+            prefix: true,
+            argument: Box::new(expr),
+        }),
+    }
+}
+
+fn is_loop(stmt: &Stmt) -> bool {
+    match stmt {
+        Stmt::For(_) | Stmt::ForIn(_) | Stmt::ForOf(_) | Stmt::While(_) | Stmt::DoWhile(_) => true,
+        Stmt::Labeled(LabeledStmt { body, .. }) => is_loop(body),
+        _ => false,
+    }
+}
+
+// Puts `label` on the loop that a lowered loop ends with, looking through any
+// block that was added around it.
+fn label_loop<'a>(label: Ident<'a>, stmt: Stmt<'a>) -> Stmt<'a> {
+    match stmt {
+        Stmt::Block(BlockStmt(mut parts)) => match parts.pop() {
+            Some(ProgramPart::Stmt(last)) if is_loop(&last) => {
+                parts.push(ProgramPart::Stmt(label_loop(label, last)));
+                Stmt::Block(BlockStmt(parts))
+            }
+            last => {
+                parts.extend(last);
+                Stmt::Labeled(LabeledStmt {
+                    label,
+                    body: Box::new(Stmt::Block(BlockStmt(parts))),
+                })
+            }
+        },
+        stmt => Stmt::Labeled(LabeledStmt {
+            label,
+            body: Box::new(stmt),
+        }),
+    }
+}
+
+// The parser reads `for ([a, b] of xs)` with an array expression on the left,
+// so it's turned back into the pattern it stands for. A member target with a
+// default (`[o.a = 1]`) has no pattern form in ResAST.
+fn expr_pat(expr: Expr) -> Option<Pat> {
+    let pat = match expr {
+        Expr::Ident(ident) => Pat::Ident(ident),
+        Expr::Array(elements) => Pat::Array(
+            elements
+                .into_iter()
+                .map(|element| match element {
+                    Some(Expr::Member(member)) => {
+                        Some(Some(ArrayPatPart::Expr(Expr::Member(member))))
+                    }
+                    Some(Expr::Spread(inner)) => {
+                        let rest = Pat::RestElement(Box::new(expr_pat(*inner)?));
+                        Some(Some(ArrayPatPart::Pat(rest)))
+                    }
+                    Some(element) => Some(Some(ArrayPatPart::Pat(expr_pat(element)?))),
+                    None => Some(None),
+                })
+                .collect::<Option<_>>()?,
+        ),
+        Expr::Obj(props) => Pat::Obj(
+            props
+                .into_iter()
+                .map(|prop| match prop {
+                    ObjProp::Prop(prop) => {
+                        let key = match prop.key {
+                            PropKey::Expr(Expr::Ident(key)) if !prop.computed => {
+                                PropKey::Pat(Pat::Ident(key))
+                            }
+                            key => key,
+                        };
+                        let value = match prop.value {
+                            PropValue::Expr(value @ Expr::Member(_)) => PropValue::Expr(value),
+                            PropValue::Expr(value) => PropValue::Pat(expr_pat(value)?),
+                            value => value,
+                        };
+                        Some(ObjPatPart::Assign(Prop { key, value, ..prop }))
+                    }
+                    ObjProp::Spread(Expr::Spread(inner)) => {
+                        Some(ObjPatPart::Rest(Box::new(expr_pat(*inner)?)))
+                    }
+                    ObjProp::Spread(inner) => Some(ObjPatPart::Rest(Box::new(expr_pat(inner)?))),
+                })
+                .collect::<Option<_>>()?,
+        ),
+        Expr::Assign(AssignExpr {
+            operator: AssignOp::Equal,
+            left,
+            right,
+        }) => Pat::Assign(AssignPat {
+            left: Box::new(match left {
+                AssignLeft::Pat(pat) => pat,
+                AssignLeft::Expr(left) => expr_pat(*left)?,
+            }),
+            right,
+        }),
+        _ => return None,
+    };
+    Some(pat)
+}
+
+// `if (!test) { break; }`
+fn break_unless(test: Expr) -> Line {
+    Line::Part(ProgramPart::Stmt(Stmt::If(IfStmt {
+        test: negate(test),
+        consequent: Box::new(Stmt::Block(BlockStmt(vec![ProgramPart::Stmt(
+            Stmt::Break(None),
+        )]))),
+        alternate: None,
+    })))
+}
+
+fn wrap(mut block: Block) -> Box<Stmt> {
+    // A block that was kept for its scope is one already.
+    if block.value.is_none() && block.action_lines.len() == 1 {
+        match block.action_lines.pop() {
+            Some(Line::Part(ProgramPart::Stmt(stmt @ Stmt::Block(_)))) => return Box::new(stmt),
+            line => block.action_lines.extend(line),
+        }
+    }
+    Box::new(Stmt::Block(block.block()))
+}
+
+// Finds the keys, defaults and targets of a pattern that would hoist lines,
+// for `Blockifier::pattern`.
+struct PatHoists<'s, 'b> {
+    blockifier: &'s Blockifier<'b>,
+    found: bool,
+}
+
+impl<'a, 's, 'b> Visit<'a> for PatHoists<'s, 'b> {
+    fn visit_expr(&mut self, expr: &Expr<'a>) {
+        self.found |= self.blockifier.hoists(expr);
+    }
+}
+
+#[derive(Debug)]
+struct Block<'a> {
+    action_lines: Vec<Line<'a>>,
+    value: Option<Expr<'a>>,
+}
+
+impl<'a> Block<'a> {
+    pub fn from_value(value: Expr<'a>) -> Block<'a> {
+        Block {
+            action_lines: vec![],
+            value: Some(value),
+        }
+    }
+    pub fn from_lines(action_lines: Vec<Line<'a>>) -> Block<'a> {
+        Block {
+            action_lines,
+            value: None,
+        }
+    }
+    pub fn value(&mut self) -> Option<Expr<'a>> {
+        self.value.take()
+    }
+    pub fn expr(&mut self) -> Expr<'a> {
+        self.value().unwrap_or(Expr::Lit(Lit::Null))
+    }
+    pub fn lines(self) -> Vec<Line<'a>> {
+        let mut action_lines = self.action_lines;
+        if let Some(expr) = self.value {
+            action_lines.push(Line::Part(ProgramPart::Stmt(Stmt::Expr(expr))))
+        }
+        action_lines
+    }
+    pub fn program(self) -> resast::Program<'a> {
+        let lines = self.lines();
+        let parts = lines
+            .into_iter()
+            .filter_map(|line| match line {
+                Line::Part(ProgramPart::Stmt(Stmt::Empty)) => None,
+                Line::Part(part) => Some(part),
+                Line::Variable(name, expr) => Some(ProgramPart::Decl(Decl::Var(
+                    VarKind::Const,
+                    vec![VarDecl {
+                        id: Pat::Ident(Ident { name }),
+                        init: expr,
+                    }],
+                ))),
+            })
+            .collect();
+        Program::Script(parts)
+    }
+    pub fn block(self) -> BlockStmt<'a> {
+        match self.program() {
+            Program::Script(parts) => BlockStmt(parts),
+            Program::Mod(parts) => BlockStmt(parts),
+        }
+    }
+}
+
+// Lowers nested expressions into statements ("lines") that run before the
+// value that's left over.
+//
+// Evaluation order is kept left to right: when a later operand hoists lines,
+// every earlier operand whose value those lines could change is stored in a
+// temp first (see `Blockifier::in_order`). Literals, functions, `this` and
+// `super` never need one, and neither does a plain variable that the lines
+// don't assign to directly.
+struct Blockifier<'b> {
+    namer: Box<dyn Namer + 'b>,
+    // Every identifier in the program, and every temp handed out so far.
+    taken: HashSet<String>,
+    // Just the temps, for the cleanup passes that run afterwards.
+    temps: HashSet<String>,
+    // Decides which unused values can be left out.
+    purity: Purity,
+    options: Options,
+    // `var` temps of each function being blockified, innermost last.
+    hoisted: Vec<Vec<String>>,
+    // What `uncaptured` found for each function being blockified, innermost
+    // last. The program's own variables are never on it, since any function
+    // can change those.
+    locals: Vec<HashSet<String>>,
+}
+
+// How to deblockify. The default lowers everything it can, with the optional
+// rewrites off. The `deblockify` binary sets these from its flags.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Options {
+    // `a = b = c` becomes `b = c; a = b;`.
+    pub split_assignments: bool,
+    pub temps: Temps,
+    // What may be assumed when dropping unused values.
+    pub assumptions: Assumptions,
+    pub lowerings: Lowerings,
+}
+
+// Which constructs are lowered. One that isn't is left as an expression,
+// with its operands blockified where they hoist nothing and left as written
+// where they would.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Lowerings {
+    // `(a(), b)` becomes `a(); b`.
+    pub sequences: bool,
+    // `a && b()` becomes `if (a) { b(); }`, with a temp for its value.
+    pub logicals: bool,
+    // `a ? b() : c()` becomes an `if` and `else`, with a temp for its value.
+    pub conditionals: bool,
+    // Object literals have their keys and values hoisted.
+    pub objects: bool,
+    // Assignments to properties, compound assignments and chains have their
+    // parts hoisted, and patterns and parameters with defaults are split up.
+    // A plain `a = b` always has its right side blockified.
+    pub assignments: bool,
+}
+
+// Named sets of lowerings, from least to most.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Level {
+    // Only splits sequences, for a first look at a script.
+    Sequences,
+    // Sequences, short-circuits and conditionals.
+    Control,
+    // Everything.
+    Full,
+}
+
+impl Level {
+    pub fn lowerings(self) -> Lowerings {
+        let control = self != Level::Sequences;
+        let full = self == Level::Full;
+        Lowerings {
+            sequences: true,
+            logicals: control,
+            conditionals: control,
+            objects: full,
+            assignments: full,
+        }
+    }
+}
+
+impl Default for Lowerings {
+    fn default() -> Lowerings {
+        Level::Full.lowerings()
+    }
+}
+
+// How temps are declared.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum Temps {
+    // `const` where the temp is only assigned once, and `let` otherwise.
+    #[default]
+    Const,
+    // Always `let`.
+    Let,
+    // One `var` at the top of the enclosing function or program, so none is
+    // redeclared in a loop body. Top-level temps become properties of the
+    // global object, like any other top-level `var`.
+    Var,
+}
+
+impl<'b> Blockifier<'b> {
+    fn new(
+        namer: Box<dyn Namer + 'b>,
+        taken: HashSet<String>,
+        purity: Purity,
+        options: Options,
+    ) -> Blockifier<'b> {
+        Blockifier {
+            namer,
+            taken,
+            temps: HashSet::new(),
+            purity,
+            options,
+            hoisted: vec![],
+            locals: vec![],
+        }
+    }
+    fn blockify<'a>(&mut self, blockable: Blockable<'a>) -> Block<'a>
+    where
+        'b: 'a,
+    {
+        match blockable {
+            Blockable::Program(prog) => {
+                let parts = match prog {
+                    Program::Script(parts) => parts,
+                    Program::Mod(parts) => parts,
+                };
+                let lines: Vec<Line<'a>> = parts
+                    .into_iter()
+                    .flat_map(|part| self.blockify(Blockable::ProgramPart(part)).lines())
+                    .collect();
+                Block {
+                    action_lines: lines,
+                    value: None,
+                }
+            }
+            Blockable::ProgramPart(part) => match part {
+                ProgramPart::Stmt(stmt) => self.blockify(Blockable::Stmt(stmt)),
+                ProgramPart::Decl(decl) => match decl {
+                    Decl::Func(func) => {
+                        let decl_line = Line::Part(ProgramPart::Decl(Decl::Func(self.func(func))));
+                        Block {
+                            action_lines: vec![decl_line],
+                            value: None,
+                        }
+                    }
+                    Decl::Var(kind, decls) => {
+                        let decl_lines = decls
+                            .into_iter()
+                            .flat_map(|VarDecl { id, init }| {
+                                let mut init_block = init.map(|init| self.assigned(init));
+                                let (mut pre, id, mut post) = self.pattern(id, Some(kind));
+                                let mut lines = vec![];
+                                let init = init_block.as_mut().map(|bl| bl.expr());
+                                lines.append(
+                                    &mut init_block.map_or_else(Vec::new, |init| init.lines()),
+                                );
+                                let init = init.map(|init| self.spill(init, &mut lines, &pre));
+                                lines.append(&mut pre);
+                                lines.push(Line::Part(ProgramPart::Decl(Decl::Var(
+                                    kind,
+                                    vec![VarDecl { id, init }],
+                                ))));
+                                lines.append(&mut post);
+                                lines
+                            })
+                            .collect();
+                        Block {
+                            action_lines: decl_lines,
+                            value: None,
+                        }
+                    }
+                    Decl::Class(class) => {
+                        let (mut lines, class) = self.class(class);
+                        lines.push(Line::Part(ProgramPart::Decl(Decl::Class(class))));
+                        Block {
+                            action_lines: lines,
+                            value: None,
+                        }
+                    }
+                    decl => Block {
+                        action_lines: vec![Line::Part(ProgramPart::Decl(decl))],
+                        value: None,
+                    },
+                },
+                part => Block {
+                    action_lines: vec![Line::Part(part)],
+                    value: None,
+                },
+            },
+            Blockable::Block(BlockStmt(parts)) => {
+                let prog = Blockable::Program(Program::Script(parts));
+                self.blockify(prog)
+            }
+            Blockable::Stmt(stmt) => match stmt {
+                // Flattened into the surrounding statements, unless it has its
+                // own scope to keep.
+                Stmt::Block(block_stmt) => {
+                    let block = self.blockify(Blockable::Block(block_stmt));
+                    if self.is_scope(&block.action_lines) {
+                        Block::from_lines(vec![Line::Part(ProgramPart::Stmt(Stmt::Block(
+                            block.block(),
+                        )))])
+                    } else {
+                        block
+                    }
+                }
+                Stmt::ForIn(ForInStmt { left, right, body }) => {
+                    let (mut lines, left, right, body) = self.for_each(left, right, *body);
+                    lines.push(Line::Part(ProgramPart::Stmt(Stmt::ForIn(ForInStmt {
+                        left,
+                        right,
+                        body,
+                    }))));
+
+                    Block {
+                        action_lines: lines,
+                        value: None,
+                    }
+                }
+                Stmt::ForOf(ForOfStmt {
+                    left,
+                    right,
+                    body,
+                    is_await,
+                }) => {
+                    let (mut lines, left, right, body) = self.for_each(left, right, *body);
+                    lines.push(Line::Part(ProgramPart::Stmt(Stmt::ForOf(ForOfStmt {
+                        left,
+                        right,
+                        body,
+                        is_await,
+                    }))));
+
+                    Block {
+                        action_lines: lines,
+                        value: None,
+                    }
+                }
+                Stmt::For(ForStmt {
+                    init,
+                    test,
+                    update,
+                    body,
+                }) => {
+                    // The init runs once, so its lines go in front of the loop.
+                    // Declarations at its end stay in the head, which keeps
+                    // `let`'s per-iteration bindings.
+                    let (mut lines, mut init, kind) = match init {
+                        Some(LoopInit::Variable(kind, decls)) => {
+                            let mut bound = Bindings::default();
+                            for decl in &decls {
+                                bound.visit_pat(&decl.id);
+                            }
+                            let decl = ProgramPart::Decl(Decl::Var(kind, decls));
+                            let mut lines = self.blockify(Blockable::ProgramPart(decl)).lines();
+                            let mut head = vec![];
+                            while let Some(Line::Part(ProgramPart::Decl(Decl::Var(k, _)))) =
+                                lines.last()
+                            {
+                                if *k != kind {
+                                    break;
+                                }
+                                if let Some(Line::Part(ProgramPart::Decl(Decl::Var(_, decls)))) =
+                                    lines.pop()
+                                {
+                                    head.splice(0..0, decls);
+                                }
+                            }
+                            // A hoisted `let` would lose its per-iteration
+                            // binding, so the head redeclares it from a temp:
+                            // let t; { let k = 0; ...; t = k; } for (let k = t, ...)
+                            let mut hoisted = Bindings::default();
+                            for line in &lines {
+                                if let Line::Part(ProgramPart::Decl(Decl::Var(_, decls))) = line {
+                                    for decl in decls {
+                                        hoisted.visit_pat(&decl.id);
+                                    }
+                                }
+                            }
+                            hoisted.names.retain(|name| bound.names.contains(name));
+                            if kind == VarKind::Let && !hoisted.names.is_empty() {
+                                let mut temps = vec![];
+                                let mut copies = vec![];
+                                for name in hoisted.names {
+                                    let my_var = &self.temp(
+                                        Some(&Expr::Ident(Ident { name: name.clone() })),
+                                        "copy",
+                                    );
+                                    temps.extend(self.declare(my_var, None, true));
+                                    lines.push(Line::Part(assign_line(
+                                        my_var,
+                                        Expr::Ident(Ident { name: name.clone() }),
+                                    )));
+                                    copies.push(VarDecl {
+                                        id: Pat::Ident(Ident { name }),
+                                        init: Some(get_id(my_var)),
+                                    });
+                                }
+                                head.splice(0..0, copies);
+                                temps.push(Line::Part(ProgramPart::Stmt(Stmt::Block(
+                                    Block::from_lines(lines).block(),
+                                ))));
+                                lines = temps;
+                            }
+                            let init = if head.is_empty() {
+                                None
+                            } else {
+                                Some(LoopInit::Variable(kind, head))
+                            };
+                            (lines, init, Some(kind))
+                        }
+                        Some(LoopInit::Expr(expr)) => {
+                            let mut lines = self.effects(expr);
+                            let init = match lines.pop() {
+                                Some(Line::Part(ProgramPart::Stmt(Stmt::Expr(expr)))) => {
+                                    Some(LoopInit::Expr(expr))
+                                }
+                                last => {
+                                    lines.extend(last);
+                                    None
+                                }
+                            };
+                            (lines, init, None)
+                        }
+                        None => (vec![], None, None),
+                    };
+
+                    let t_block = test.map(|test| self.blockify(Blockable::Expr(test)));
+                    let mut u_lines = update.map_or_else(Vec::new, |update| self.effects(update));
+                    let body_block = self.blockify(Blockable::Stmt(*body));
+
+                    let mut update = match u_lines.pop() {
+                        Some(Line::Part(ProgramPart::Stmt(Stmt::Expr(expr))))
+                            if u_lines.is_empty() =>
+                        {
+                            Some(expr)
+                        }
+                        last => {
+                            u_lines.extend(last);
+                            None
+                        }
+                    };
+
+                    let mut body_lines = vec![];
+                    let hoists_update = !u_lines.is_empty();
+                    if hoists_update {
+                        // for (...; ; first = false) {
+                        //     if (!first) { ...update lines }
+                        //     ...test lines; if (!test) break;
+                        //     ...body
+                        // }
+                        // `continue` still runs the update and the test.
+                        let my_var = &self.temp_for(&(&t_block, &u_lines), "first");
+                        body_lines.push(Line::Part(ProgramPart::Stmt(Stmt::If(IfStmt {
+                            test: negate(get_id(my_var)),
+                            consequent: wrap(Block::from_lines(u_lines)),
+                            alternate: None,
+                        }))));
+                        update = Some(Expr::Assign(AssignExpr {
+                            operator: AssignOp::Equal,
+                            left: AssignLeft::Expr(Box::new(get_id(my_var))),
+                            right: Box::new(Expr::Lit(Lit::Boolean(false))),
+                        }));
+                        let flag = VarDecl {
+                            id: Pat::Ident(get_id_pat(my_var)),
+                            init: Some(Expr::Lit(Lit::Boolean(true))),
+                        };
+                        match &mut init {
+                            None => init = Some(self.flag_init(my_var)),
+                            Some(LoopInit::Variable(VarKind::Let, decls))
+                                if self.options.temps != Temps::Var =>
+                            {
+                                decls.push(flag)
+                            }
+                            Some(_) => lines.extend(self.declare(my_var, flag.init, true)),
+                        }
+                    }
+
+                    // Test lines have to run before every iteration, after
+                    // the update.
+                    let test = match t_block {
+                        Some(mut t_block) if hoists_update || !t_block.action_lines.is_empty() => {
+                            let test = t_block.expr();
+                            body_lines.append(&mut t_block.lines());
+                            body_lines.push(break_unless(test));
+                            None
+                        }
+                        Some(mut t_block) => Some(t_block.expr()),
+                        None => None,
+                    };
+                    body_lines.append(&mut body_block.lines());
+
+                    // `let` and `const` bindings that had to leave the head
+                    // are kept out of the enclosing scope by a block.
+                    let needs_scope = matches!(kind, Some(VarKind::Let) | Some(VarKind::Const))
+                        && lines
+                            .iter()
+                            .any(|line| matches!(line, Line::Part(ProgramPart::Decl(_))));
+
+                    lines.push(Line::Part(ProgramPart::Stmt(Stmt::For(ForStmt {
+                        init,
+                        test,
+                        update,
+                        body: wrap(Block::from_lines(body_lines)),
+                    }))));
+
+                    let action_lines = if needs_scope {
+                        vec![Line::Part(ProgramPart::Stmt(Stmt::Block(
+                            Block::from_lines(lines).block(),
+                        )))]
+                    } else {
+                        lines
+                    };
+
+                    Block {
+                        action_lines,
+                        value: None,
+                    }
+                }
+                Stmt::While(WhileStmt { test, body }) => {
+                    let mut t_block = self.blockify(Blockable::Expr(test));
+                    let body_block = self.blockify(Blockable::Stmt(*body));
+
+                    let test = t_block.expr();
+                    let while_st = if t_block.action_lines.is_empty() {
+                        Stmt::While(WhileStmt {
+                            test,
+                            body: wrap(body_block),
+                        })
+                    } else {
+                        // The test's lines have to run before every iteration.
+                        let mut lines = t_block.lines();
+                        lines.push(break_unless(test));
+                        lines.append(&mut body_block.lines());
+                        Stmt::While(WhileStmt {
+                            test: Expr::Lit(Lit::Boolean(true)),
+                            body: wrap(Block::from_lines(lines)),
+                        })
+                    };
+
+                    Block {
+                        action_lines: vec![Line::Part(ProgramPart::Stmt(while_st))],
+                        value: None,
+                    }
+                }
+                Stmt::DoWhile(DoWhileStmt { test, body }) => {
+                    let body_block = self.blockify(Blockable::Stmt(*body));
+                    let mut t_block = self.blockify(Blockable::Expr(test));
+
+                    let test = t_block.expr();
+                    let loop_st = if t_block.action_lines.is_empty() {
+                        Stmt::DoWhile(DoWhileStmt {
+                            test,
+                            body: wrap(body_block),
+                        })
+                    } else {
+                        // for (let first = true; ; first = false) {
+                        //     if (!first) { ...lines; if (!test) break; }
+                        //     ...body
+                        // }
+                        // Unlike moving the test to the end of the body, this
+                        // still runs the test when the body hits `continue`.
+                        let my_var = &self.temp_for(&(&t_block, &test), "first");
+
+                        let mut t_lines = t_block.lines();
+                        t_lines.push(break_unless(test));
+                        let check = Line::Part(ProgramPart::Stmt(Stmt::If(IfStmt {
+                            test: negate(get_id(my_var)),
+                            consequent: wrap(Block::from_lines(t_lines)),
+                            alternate: None,
+                        })));
+
+                        let mut lines = vec![check];
+                        lines.append(&mut body_block.lines());
+
+                        Stmt::For(ForStmt {
+                            init: Some(self.flag_init(my_var)),
+                            test: None,
+                            update: Some(Expr::Assign(AssignExpr {
+                                operator: AssignOp::Equal,
+                                left: AssignLeft::Expr(Box::new(get_id(my_var))),
+                                right: Box::new(Expr::Lit(Lit::Boolean(false))),
+                            })),
+                            body: wrap(Block::from_lines(lines)),
+                        })
+                    };
+
+                    Block {
+                        action_lines: vec![Line::Part(ProgramPart::Stmt(loop_st))],
+                        value: None,
+                    }
+                }
+                Stmt::If(IfStmt {
+                    test,
+                    consequent,
+                    alternate,
+                }) => {
+                    let mut t_block = self.blockify(Blockable::Expr(test));
+                    let consequent = self.blockify(Blockable::Stmt(*consequent));
+                    let test = t_block.expr();
+                    let mut lines = t_block.lines();
+                    let alternate = match alternate {
+                        Some(alternate) => {
+                            let alternate = self.blockify(Blockable::Stmt(*alternate));
+                            Some(self.else_branch(alternate, &test, &consequent, &mut lines))
+                        }
+                        None => None,
+                    };
+                    lines.push(Line::Part(ProgramPart::Stmt(Stmt::If(IfStmt {
+                        test,
+                        consequent: wrap(consequent),
+                        alternate,
+                    }))));
+                    Block {
+                        action_lines: lines,
+                        value: None,
+                    }
+                }
+                Stmt::Labeled(LabeledStmt { label, body }) => {
+                    let keeps_label = is_loop(&body) || matches!(*body, Stmt::Switch(_));
+                    let mut lines = self.blockify(Blockable::Stmt(*body)).lines();
+
+                    // `continue label` needs the label right on the loop, so any
+                    // lines hoisted out of the loop's head stay outside it.
+                    let last = if keeps_label { lines.pop() } else { None };
+                    let action_lines = match last {
+                        Some(Line::Part(ProgramPart::Stmt(stmt))) => {
+                            lines.push(Line::Part(ProgramPart::Stmt(label_loop(label, stmt))));
+                            lines
+                        }
+                        last => {
+                            // Anything else only allows `break label`, which
+                            // can leave a block just as well.
+                            lines.extend(last);
+                            let body = match lines.pop() {
+                                Some(Line::Part(ProgramPart::Stmt(stmt))) if lines.is_empty() => {
+                                    Box::new(stmt)
+                                }
+                                last => {
+                                    lines.extend(last);
+                                    wrap(Block::from_lines(lines))
+                                }
+                            };
+                            vec![Line::Part(ProgramPart::Stmt(Stmt::Labeled(LabeledStmt {
+                                label,
+                                body,
+                            })))]
+                        }
+                    };
+
+                    Block {
+                        action_lines,
+                        value: None,
+                    }
+                }
+                Stmt::Switch(SwitchStmt {
+                    discriminant,
+                    cases,
+                }) => {
+                    let mut d_block = self.blockify(Blockable::Expr(discriminant));
+
+                    let mut t_blocks: Vec<Option<Block<'a>>> = vec![];
+                    let mut bodies: Vec<Vec<ProgramPart<'a>>> = vec![];
+                    for SwitchCase { test, consequent } in cases {
+                        t_blocks.push(test.map(|test| self.blockify(Blockable::Expr(test))));
+                        let body = self.blockify(Blockable::Program(Program::Script(consequent)));
+                        bodies.push(match body.program() {
+                            Program::Script(parts) => parts,
+                            Program::Mod(parts) => parts,
+                        });
+                    }
+
+                    let discriminant = d_block.expr();
+                    let mut lines = d_block.lines();
+
+                    let hoists_tests = t_blocks
+                        .iter()
+                        .flatten()
+                        .any(|t_block| !t_block.action_lines.is_empty());
+
+                    let switch_st = if !hoists_tests {
+                        Stmt::Switch(SwitchStmt {
+                            discriminant,
+                            cases: t_blocks
+                                .into_iter()
+                                .zip(bodies)
+                                .map(|(t_block, consequent)| SwitchCase {
+                                    test: t_block.map(|mut t_block| t_block.expr()),
+                                    consequent,
+                                })
+                                .collect(),
+                        })
+                    } else {
+                        // Case tests are only evaluated until one matches, so
+                        // they can't all be hoisted. Instead, work out which
+                        // case matches with an if/else chain, then switch on
+                        // its index. `default` is taken when nothing is set.
+                        let d_var = &self.temp(Some(&discriminant), "subject");
+                        let k_var = &self.temp_for(&(&discriminant, &t_blocks), "case");
+                        lines.extend(self.declare(d_var, Some(discriminant), false));
+                        lines.extend(self.declare(k_var, None, true));
+
+                        let case_num = |idx: usize| Expr::Lit(Lit::Number(idx.to_string().into()));
+
+                        let has_test: Vec<bool> = t_blocks.iter().map(Option::is_some).collect();
+
+                        let mut chain: Option<Stmt<'a>> = None;
+                        for (idx, t_block) in t_blocks.into_iter().enumerate().rev() {
+                            if let Some(mut t_block) = t_block {
+                                let if_st = Stmt::If(IfStmt {
+                                    test: Expr::Binary(BinaryExpr {
+                                        operator: BinaryOp::StrictEqual,
+                                        left: Box::new(get_id(d_var)),
+                                        right: Box::new(t_block.expr()),
+                                    }),
+                                    consequent: Box::new(Stmt::Block(BlockStmt(vec![
+                                        assign_line(k_var, case_num(idx)),
+                                    ]))),
+                                    alternate: chain.map(Box::new),
+                                });
+                                chain = Some(if t_block.action_lines.is_empty() {
+                                    if_st
+                                } else {
+                                    let mut t_lines = t_block.lines();
+                                    t_lines.push(Line::Part(ProgramPart::Stmt(if_st)));
+                                    Stmt::Block(Block::from_lines(t_lines).block())
+                                });
+                            }
+                        }
+                        match chain {
+                            Some(Stmt::Block(BlockStmt(parts))) => {
+                                lines.extend(parts.into_iter().map(Line::Part))
+                            }
+                            Some(stmt) => lines.push(Line::Part(ProgramPart::Stmt(stmt))),
+                            None => {}
+                        }
+
+                        Stmt::Switch(SwitchStmt {
+                            discriminant: get_id(k_var),
+                            cases: has_test
+                                .into_iter()
+                                .zip(bodies)
+                                .enumerate()
+                                .map(|(idx, (has_test, consequent))| SwitchCase {
+                                    test: if has_test { Some(case_num(idx)) } else { None },
+                                    consequent,
+                                })
+                                .collect(),
+                        })
+                    };
+
+                    lines.push(Line::Part(ProgramPart::Stmt(switch_st)));
+                    Block {
+                        action_lines: lines,
+                        value: None,
+                    }
+                }
+                Stmt::Try(TryStmt {
+                    block,
+                    handler,
+                    finalizer,
+                }) => {
+                    let block = self.blockify(Blockable::Block(block)).block();
+                    let handler = handler.map(|CatchClause { param, body }| CatchClause {
+                        body: self.blockify(Blockable::Block(body)).block(),
+                        param,
+                    });
+                    let finalizer = finalizer.map(|f| self.blockify(Blockable::Block(f)).block());
+                    let stmt = Line::Part(ProgramPart::Stmt(Stmt::Try(TryStmt {
+                        block,
+                        handler,
+                        finalizer,
+                    })));
+                    Block {
+                        action_lines: vec![stmt],
+                        value: None,
+                    }
+                }
+                Stmt::Return(expr_opt) => Block {
+                    value: None,
+                    action_lines: match expr_opt {
+                        Some(expr) => {
+                            let mut block = self.blockify(Blockable::Expr(expr));
+                            let ret = Line::Part(ProgramPart::Stmt(Stmt::Return(block.value())));
+                            let mut lines = block.lines();
+                            lines.push(ret);
+                            lines
+                        }
+                        None => vec![Line::Part(ProgramPart::Stmt(Stmt::Return(None)))],
+                    },
+                },
+                Stmt::Expr(expr) => Block {
+                    action_lines: self.effects(expr),
+                    value: None,
+                },
+                stmt => Block {
+                    action_lines: vec![Line::Part(ProgramPart::Stmt(stmt))],
+                    value: None,
+                },
+            },
+            Blockable::Expr(expr) => {
+                match expr {
+                    Expr::Array(exprs) => {
+                        let holes: Vec<bool> = exprs.iter().map(Option::is_none).collect();
+                        let blocks = exprs
+                            .into_iter()
+                            .flatten()
+                            .map(|expr| self.blockify(Blockable::Expr(expr)))
+                            .collect();
+                        let (lines, values) = self.in_order(blocks, &[]);
+                        let mut values = values.into_iter();
+
+                        let arr = Expr::Array(
+                            holes
+                                .into_iter()
+                                .map(|hole| if hole { None } else { values.next() })
+                                .collect(),
+                        );
+
+                        Block {
+                            action_lines: lines,
+                            value: Some(arr),
+                        }
+                    }
+                    Expr::Obj(props) if !self.options.lowerings.objects => {
+                        let props = props
+                            .into_iter()
+                            .map(|prop| match prop {
+                                ObjProp::Spread(Expr::Spread(inner)) => {
+                                    ObjProp::Spread(Expr::Spread(Box::new(self.as_expr(*inner))))
+                                }
+                                ObjProp::Spread(expr) => ObjProp::Spread(self.as_expr(expr)),
+                                ObjProp::Prop(prop) => {
+                                    let key = match prop.key {
+                                        PropKey::Expr(key) if prop.computed => {
+                                            PropKey::Expr(self.as_expr(key))
+                                        }
+                                        key => key,
+                                    };
+                                    let value = match prop.value {
+                                        PropValue::Expr(value) => {
+                                            PropValue::Expr(self.as_expr(value))
+                                        }
+                                        value => value,
+                                    };
+                                    ObjProp::Prop(Prop { key, value, ..prop })
+                                }
+                            })
+                            .collect();
+                        Block::from_value(Expr::Obj(props))
+                    }
+                    Expr::Obj(props) => {
+                        // Keys and values are evaluated in source order, so the
+                        // literal stays whole and only values that later lines
+                        // could change are moved into temps.
+                        // `this` stands in for each hoisted key and value until
+                        // they're filled back in below.
+                        //
+                        // A computed key is converted to a string or symbol as
+                        // soon as it's evaluated, which can run code when it
+                        // isn't a literal. So once there's one of those, the
+                        // parts after it are kept in place, as `as_expr` does.
+                        let mut blocks = vec![];
+                        let mut in_place = false;
+                        let props: Vec<ObjProp<'a>> = props
+                            .into_iter()
+                            .map(|prop| match prop {
+                                ObjProp::Spread(expr) => {
+                                    let inner = match expr {
+                                        Expr::Spread(inner) => *inner,
+                                        expr => expr,
+                                    };
+                                    // Spreading copies the properties right
+                                    // here, so it's done as `{...x}`, which is
+                                    // what goes in a temp if later lines could
+                                    // change them.
+                                    let mut block = self.operand(inner, in_place);
+                                    let copy = Expr::Obj(vec![ObjProp::Spread(Expr::Spread(
+                                        Box::new(block.expr()),
+                                    ))]);
+                                    blocks.push(Block {
+                                        action_lines: block.action_lines,
+                                        value: Some(copy),
+                                    });
+                                    ObjProp::Spread(Expr::Spread(Box::new(Expr::This)))
+                                }
+                                ObjProp::Prop(prop) => {
+                                    let key = match prop.key {
+                                        PropKey::Expr(key) if prop.computed => {
+                                            let block = self.operand(key, in_place);
+                                            in_place |= block.value.as_ref().is_some_and(converts);
+                                            blocks.push(block);
+                                            PropKey::Expr(Expr::This)
+                                        }
+                                        PropKey::Pat(Pat::Ident(key)) if prop.computed => {
+                                            in_place = true;
+                                            blocks.push(Block::from_value(Expr::Ident(key)));
+                                            PropKey::Expr(Expr::This)
+                                        }
+                                        key => key,
+                                    };
+                                    let value = match prop.value {
+                                        PropValue::Expr(value) => {
+                                            blocks.push(self.operand(value, in_place));
+                                            PropValue::Expr(Expr::This)
+                                        }
+                                        PropValue::None => {
+                                            // `{a}` reads `a` right here.
+                                            if let PropKey::Pat(Pat::Ident(key)) = &key {
+                                                blocks.push(Block::from_value(Expr::Ident(
+                                                    key.clone(),
+                                                )));
+                                            }
+                                            PropValue::None
+                                        }
+                                        value => value,
+                                    };
+                                    ObjProp::Prop(Prop { key, value, ..prop })
+                                }
+                            })
+                            .collect();
+
+                        let (lines, values) = self.in_order(blocks, &[]);
+                        let mut values = values.into_iter();
+                        let mut next = || values.next().expect("One value per slot");
+
+                        let props = props
+                            .into_iter()
+                            .map(|prop| match prop {
+                                ObjProp::Spread(_) => match next() {
+                                    Expr::Obj(mut copy)
+                                        if matches!(copy[..], [ObjProp::Spread(_)]) =>
+                                    {
+                                        copy.remove(0)
+                                    }
+                                    value => ObjProp::Spread(Expr::Spread(Box::new(value))),
+                                },
+                                ObjProp::Prop(prop) => {
+                                    let key = match prop.key {
+                                        PropKey::Expr(_) if prop.computed => PropKey::Expr(next()),
+                                        key => key,
+                                    };
+                                    match (key, prop.value) {
+                                        (key, PropValue::Expr(_)) => {
+                                            let value = PropValue::Expr(next());
+                                            ObjProp::Prop(Prop { key, value, ..prop })
+                                        }
+                                        (PropKey::Pat(Pat::Ident(key)), PropValue::None) => {
+                                            match next() {
+                                                Expr::Ident(value) if value == key => {
+                                                    ObjProp::Prop(Prop {
+                                                        key: PropKey::Pat(Pat::Ident(key)),
+                                                        value: PropValue::None,
+                                                        ..prop
+                                                    })
+                                                }
+                                                // Spilled, so `{a}` becomes `{a: temp}`.
+                                                value => {
+                                                    let (key, computed) = own_key(key);
+                                                    ObjProp::Prop(Prop {
+                                                        key,
+                                                        value: PropValue::Expr(value),
+                                                        short_hand: false,
+                                                        computed,
+                                                        ..prop
+                                                    })
+                                                }
+                                            }
+                                        }
+                                        (key, value) => ObjProp::Prop(Prop { key, value, ..prop }),
+                                    }
+                                }
+                            })
+                            .collect();
+
+                        Block {
+                            action_lines: lines,
+                            value: Some(Expr::Obj(props)),
+                        }
+                    }
+                    Expr::Func(func) => Block {
+                        action_lines: vec![],
+                        value: Some(Expr::Func(self.func(func))),
+                    },
+                    Expr::ArrowFunc(ArrowFuncExpr {
+                        id,
+                        params,
+                        body,
+                        expression,
+                        generator,
+                        is_async,
+                    }) => {
+                        let (params, body, expression) = match body {
+                            ArrowFuncBody::FuncBody(body) => {
+                                let (params, body) = self.func_body(params, body, generator);
+                                (params, ArrowFuncBody::FuncBody(body), expression)
+                            }
+                            ArrowFuncBody::Expr(expr) => {
+                                self.hoisted.push(vec![]);
+                                self.locals.push(uncaptured(&params, &[], Some(&expr)));
+                                let (params, p_lines) =
+                                    self.params(params, &HashSet::new(), generator);
+                                let mut block = self.blockify(Blockable::Expr(*expr));
+                                block.action_lines.splice(0..0, p_lines);
+                                let mut block = self.close_scope(block);
+                                self.locals.pop();
+                                let (body, expression) = if block.action_lines.is_empty() {
+                                    (ArrowFuncBody::Expr(Box::new(block.expr())), true)
+                                } else {
+                                    // Hoisted lines need a block body to live in.
+                                    let ret =
+                                        Line::Part(ProgramPart::Stmt(Stmt::Return(block.value())));
+                                    let mut lines = block.lines();
+                                    lines.push(ret);
+                                    let BlockStmt(parts) = Block::from_lines(lines).block();
+                                    (ArrowFuncBody::FuncBody(FuncBody(parts)), false)
+                                };
+                                (params, body, expression)
+                            }
+                        };
+                        Block {
+                            action_lines: vec![],
+                            value: Some(Expr::ArrowFunc(ArrowFuncExpr {
+                                id,
+                                params,
+                                body,
+                                expression,
+                                generator,
+                                is_async,
+                            })),
+                        }
+                    }
+                    Expr::Class(class) => {
+                        let (lines, class) = self.class(class);
+                        Block {
+                            action_lines: lines,
+                            value: Some(Expr::Class(class)),
+                        }
+                    }
+                    Expr::Unary(UnaryExpr {
+                        operator,
+                        prefix,
+                        argument,
+                    }) => {
+                        let mut argument = self.blockify(Blockable::Expr(*argument));
+                        Block {
+                            value: Some(Expr::Unary(UnaryExpr {
+                                operator,
+                                prefix,
+                                argument: Box::new(argument.expr()),
+                            })),
+                            action_lines: argument.lines(),
+                        }
+                    }
+                    Expr::Logical(logical) => self.logical(logical),
+                    Expr::Conditional(conditional) => self.conditional(conditional),
+                    Expr::Binary(BinaryExpr {
+                        left,
+                        right,
+                        operator,
+                    }) => {
+                        let l_block = self.blockify(Blockable::Expr(*left));
+                        let r_block = self.blockify(Blockable::Expr(*right));
+
+                        let (lines, mut values) = self.in_order(vec![l_block, r_block], &[]);
+                        let right = values.pop().expect("Binary has a right operand");
+                        let left = values.pop().expect("Binary has a left operand");
+
+                        Block {
+                            value: Some(Expr::Binary(BinaryExpr {
+                                left: Box::new(left),
+                                right: Box::new(right),
+                                operator,
+                            })),
+                            action_lines: lines,
+                        }
+                    }
+                    Expr::Sequence(seq) if !self.options.lowerings.sequences => {
+                        let seq = seq.into_iter().map(|expr| self.as_expr(expr)).collect();
+                        Block::from_value(Expr::Sequence(seq))
+                    }
+                    Expr::Sequence(mut seq) => {
+                        let last = seq.pop();
+
+                        // Only the final expression's value is used.
+                        let mut action_lines: Vec<Line<'a>> = vec![];
+                        for expr in seq {
+                            action_lines.append(&mut self.effects(expr));
+                        }
+
+                        let value = last.and_then(|expr| {
+                            let mut expr_block = self.blockify(Blockable::Expr(expr));
+                            let value = expr_block.value();
+                            action_lines.append(&mut expr_block.lines());
+                            value
+                        });
+
+                        Block {
+                            action_lines,
+                            value,
+                        }
+                    }
+                    Expr::Call(CallExpr { callee, arguments }) => {
+                        let (mut blocks, callee) = self.callee(*callee);
+                        let callee_count = blocks.len();
+
+                        blocks.extend(
+                            arguments
+                                .into_iter()
+                                .map(|arg| self.blockify(Blockable::Expr(arg))),
+                        );
+
+                        let (action_lines, mut values) = self.in_order(blocks, &[]);
+                        let arguments = values.split_off(callee_count);
+
+                        Block {
+                            action_lines,
+                            value: Some(Expr::Call(CallExpr {
+                                callee: Box::new(callee.rebuild(values)),
+                                arguments,
+                            })),
+                        }
+                    }
+                    Expr::New(NewExpr { callee, arguments }) => {
+                        let blocks = iter::once(*callee)
+                            .chain(arguments)
+                            .map(|expr| self.blockify(Blockable::Expr(expr)))
+                            .collect();
+                        let (action_lines, mut values) = self.in_order(blocks, &[]);
+                        let arguments = values.split_off(1);
+
+                        Block {
+                            action_lines,
+                            value: Some(Expr::New(NewExpr {
+                                callee: Box::new(values.pop().expect("New has a callee")),
+                                arguments,
+                            })),
+                        }
+                    }
+                    // Each substitution is converted to a string before the
+                    // next one is evaluated, so it's spilled as `${value}`,
+                    // and unwrapped again if it isn't.
+                    Expr::Lit(Lit::Template(TemplateLit {
+                        quasis,
+                        expressions,
+                    })) => {
+                        let blocks = expressions
+                            .into_iter()
+                            .map(|expr| {
+                                let mut block = self.blockify(Blockable::Expr(expr));
+                                block.value = match block.expr() {
+                                    value @ Expr::Lit(Lit::String(_) | Lit::Number(_)) => {
+                                        Some(value)
+                                    }
+                                    value => Some(to_string(value)),
+                                };
+                                block
+                            })
+                            .collect();
+                        let (action_lines, expressions) = self.in_order(blocks, &[]);
+                        let expressions = expressions.into_iter().map(unwrap_string).collect();
+
+                        Block {
+                            action_lines,
+                            value: Some(Expr::Lit(Lit::Template(TemplateLit {
+                                quasis,
+                                expressions,
+                            }))),
+                        }
+                    }
+                    // The tag is called like a callee, so `obj.tag` keeps `obj`.
+                    Expr::TaggedTemplate(TaggedTemplateExpr {
+                        tag,
+                        quasi:
+                            TemplateLit {
+                                quasis,
+                                expressions,
+                            },
+                    }) => {
+                        let (mut blocks, tag) = self.callee(*tag);
+                        let tag_count = blocks.len();
+
+                        blocks.extend(
+                            expressions
+                                .into_iter()
+                                .map(|expr| self.blockify(Blockable::Expr(expr))),
+                        );
+
+                        let (action_lines, mut values) = self.in_order(blocks, &[]);
+                        let expressions = values.split_off(tag_count);
+
+                        Block {
+                            action_lines,
+                            value: Some(Expr::TaggedTemplate(TaggedTemplateExpr {
+                                tag: Box::new(tag.rebuild(values)),
+                                quasi: TemplateLit {
+                                    quasis,
+                                    expressions,
+                                },
+                            })),
+                        }
+                    }
+                    // The argument's lines run first, and the await or yield
+                    // stays in the value, after them.
+                    Expr::Await(argument) => {
+                        let mut a_block = self.blockify(Blockable::Expr(*argument));
+                        Block {
+                            value: Some(Expr::Await(Box::new(a_block.expr()))),
+                            action_lines: a_block.lines(),
+                        }
+                    }
+                    Expr::Yield(YieldExpr {
+                        argument: Some(argument),
+                        delegate,
+                    }) => {
+                        let mut a_block = self.blockify(Blockable::Expr(*argument));
+                        Block {
+                            value: Some(Expr::Yield(YieldExpr {
+                                argument: Some(Box::new(a_block.expr())),
+                                delegate,
+                            })),
+                            action_lines: a_block.lines(),
+                        }
+                    }
+                    // See `in_order` for how a spread is spilled.
+                    Expr::Spread(argument) => {
+                        let mut a_block = self.blockify(Blockable::Expr(*argument));
+                        Block {
+                            value: Some(Expr::Spread(Box::new(a_block.expr()))),
+                            action_lines: a_block.lines(),
+                        }
+                    }
+                    // A member argument comes back as a member, so it's still
+                    // a valid target.
+                    Expr::Update(UpdateExpr {
+                        operator,
+                        argument,
+                        prefix,
+                    }) => {
+                        let mut a_block = self.blockify(Blockable::Expr(*argument));
+                        Block {
+                            value: Some(Expr::Update(UpdateExpr {
+                                operator,
+                                argument: Box::new(a_block.expr()),
+                                prefix,
+                            })),
+                            action_lines: a_block.lines(),
+                        }
+                    }
+                    Expr::Assign(assign) => self.assign(assign, true),
+                    Expr::Member(MemberExpr {
+                        object,
+                        property,
+                        computed,
+                    }) => {
+                        let (blocks, property) = self.member_parts(*object, *property, computed);
+                        let (lines, values) = self.in_order(blocks, &[]);
+                        Block {
+                            action_lines: lines,
+                            value: Some(member(values, property, computed)),
+                        }
+                    }
+                    expr => Block {
+                        action_lines: vec![],
+                        value: Some(expr),
+                    },
+                }
+            }
+        }
+    }
+    // Blockifies a function's parameters and body together, since lines
+    // for the parameters can end up at the top of the body.
+    fn func_body<'a>(
+        &mut self,
+        params: Vec<FuncArg<'a>>,
+        body: FuncBody<'a>,
+        generator: bool,
+    ) -> (Vec<FuncArg<'a>>, FuncBody<'a>)
+    where
+        'b: 'a,
+    {
+        let FuncBody(parts) = body;
+        let mut declared = Declared::shallow();
+        for part in &parts {
+            declared.visit_part(part);
+        }
+        self.hoisted.push(vec![]);
+        self.locals.push(uncaptured(&params, &parts, None));
+        let (params, p_lines) = self.params(params, &declared.names, generator);
+        let mut block = self.blockify(Blockable::Program(Program::Script(parts)));
+        let idx = block
+            .action_lines
+            .iter()
+            .take_while(|line| matches!(line, Line::Part(ProgramPart::Dir(_))))
+            .count();
+        block.action_lines.splice(idx..idx, p_lines);
+        let block = self.close_scope(block);
+        self.locals.pop();
+        let body = match block.program() {
+            Program::Script(parts) => FuncBody(parts),
+            Program::Mod(parts) => FuncBody(parts),
+        };
+        (params, body)
+    }
+    fn func<'a>(&mut self, func: Func<'a>) -> Func<'a>
+    where
+        'b: 'a,
+    {
+        let (params, body) = self.func_body(func.params, func.body, func.generator);
+        Func {
+            params,
+            body,
+            ..func
+        }
+    }
+    // Defaults and patterns are blockified in place while they hoist
+    // nothing. From the first one that does, parameters are bound in the
+    // body instead, in order: `a = (x(), 1)` is left as `a = void 0`, so
+    // the function's `length` doesn't change, and the body starts with
+    // `if (a === void 0) { x(); a = 1; }`. A pattern is destructured from a
+    // temp that takes its place.
+    //
+    // The list is left as it is when assignments aren't lowered, for
+    // generators, whose bodies only start on the first `next()`, and when a
+    // default reads a parameter that isn't bound yet, which throws, or
+    // anything the body declares, which the default can't see. Parameters
+    // the body declares again are left too.
+    //
+    // Returns the new list and the lines for the top of the body.
+    fn params<'a>(
+        &mut self,
+        params: Vec<FuncArg<'a>>,
+        declared: &HashSet<String>,
+        generator: bool,
+    ) -> (Vec<FuncArg<'a>>, Vec<Line<'a>>)
+    where
+        'b: 'a,
+    {
+        let pats: Vec<_> = params.into_iter().map(param_pat).collect();
+        let movable = self.options.lowerings.assignments
+            && !generator
+            && pats.iter().enumerate().all(|(i, pat)| match pat {
+                Ok(pat) => {
+                    let refs = param_refs(pat);
+                    let mut later = Bindings::default();
+                    for pat in pats[i..].iter().flatten() {
+                        later.visit_pat(pat);
+                    }
+                    refs.is_disjoint(declared)
+                        && later
+                            .names
+                            .iter()
+                            .all(|name| !refs.contains(&name[..]) && !declared.contains(&name[..]))
+                }
+                Err(_) => false,
+            });
+        if !movable {
+            let params = pats
+                .into_iter()
+                .map(|pat| match pat {
+                    Ok(pat) => FuncArg::Pat(pat),
+                    Err(param) => param,
+                })
+                .collect();
+            return (params, vec![]);
+        }
+
+        let mut lines = vec![];
+        let mut moved = false;
+        let params = pats
+            .into_iter()
+            .flatten()
+            .map(|pat| FuncArg::Pat(self.param(pat, &mut moved, &mut lines)))
+            .collect();
+        (params, lines)
+    }
+    // One parameter for `params`, with `moved` set once parameters are bound
+    // in the body.
+    fn param<'a>(&mut self, pat: Pat<'a>, moved: &mut bool, lines: &mut Vec<Line<'a>>) -> Pat<'a>
+    where
+        'b: 'a,
+    {
+        match pat {
+            Pat::Ident(_) => pat,
+            Pat::RestElement(rest) => Pat::RestElement(Box::new(self.param(*rest, moved, lines))),
+            Pat::Assign(AssignPat { left, right }) if matches!(*left, Pat::Ident(_)) => {
+                let mut r_block = self.blockify(Blockable::Expr(*right));
+                if !*moved && r_block.action_lines.is_empty() {
+                    let right = Box::new(r_block.expr());
+                    return Pat::Assign(AssignPat { left, right });
+                }
+                *moved = true;
+                if let Pat::Ident(Ident { name }) = &*left {
+                    lines.push(default_line(name, r_block));
+                }
+                Pat::Assign(AssignPat {
+                    left,
+                    right: Box::new(void_0()),
+                })
+            }
+            pat => {
+                let has_default = matches!(pat, Pat::Assign(_));
+                let (mut pre, id, mut post) = self.pattern(pat, Some(VarKind::Var));
+                let in_place = !*moved && pre.is_empty();
+                if in_place && post.is_empty() {
+                    return id;
+                }
+                *moved = true;
+                match id {
+                    // Nothing was hoisted, but it has to wait for the
+                    // parameters before it.
+                    Pat::Assign(AssignPat { left, right }) => {
+                        let arg = self.temp_for(&(&left, &right), "argument");
+                        lines.push(default_line(&arg, Block::from_value(*right)));
+                        lines.push(bind_line(*left, get_id(&arg), Some(VarKind::Var)));
+                        Pat::Assign(AssignPat {
+                            left: Box::new(Pat::Ident(get_id_pat(&arg))),
+                            right: Box::new(void_0()),
+                        })
+                    }
+                    // Already a temp that the body gives its default.
+                    id if has_default => {
+                        lines.append(&mut post);
+                        Pat::Assign(AssignPat {
+                            left: Box::new(id),
+                            right: Box::new(void_0()),
+                        })
+                    }
+                    id if in_place => {
+                        lines.append(&mut post);
+                        id
+                    }
+                    id => {
+                        let arg = self.temp_for(&id, "argument");
+                        lines.append(&mut pre);
+                        lines.push(bind_line(id, get_id(&arg), Some(VarKind::Var)));
+                        lines.append(&mut post);
+                        Pat::Ident(get_id_pat(&arg))
+                    }
+                }
+            }
+        }
+    }
+    // Blockifies the body of a method, getter, setter or constructor.
+    fn method<'a>(&mut self, prop: Prop<'a>) -> Prop<'a>
+    where
+        'b: 'a,
+    {
+        match prop.value {
+            PropValue::Expr(Expr::Func(func)) => Prop {
+                value: PropValue::Expr(Expr::Func(self.func(func))),
+                ..prop
+            },
+            value => Prop { value, ..prop },
+        }
+    }
+    // The superclass and computed keys are evaluated when the class is
+    // defined, so their lines are hoisted in front of it.
+    fn class<'a>(&mut self, class: Class<'a>) -> (Vec<Line<'a>>, Class<'a>)
+    where
+        'b: 'a,
+    {
+        let Class {
+            id,
+            super_class,
+            body: ClassBody(props),
+        } = class;
+
+        // The superclass and then the computed keys are evaluated in source
+        // order, like an object literal's, with `this` standing in for each
+        // until they're filled back in. Each key is converted right after
+        // it's evaluated, so the keys after one that could run code doing
+        // that are kept in place.
+        let mut blocks = vec![];
+        let mut in_place = false;
+        let super_class = super_class.map(|super_class| {
+            blocks.push(self.blockify(Blockable::Expr(*super_class)));
+            Box::new(Expr::This)
+        });
+        let props: Vec<Prop<'a>> = props
+            .into_iter()
+            .map(|prop| {
+                let key = match prop.key {
+                    PropKey::Expr(key) if prop.computed => {
+                        let block = self.operand(key, in_place);
+                        in_place |= block.value.as_ref().is_some_and(converts);
+                        blocks.push(block);
+                        PropKey::Expr(Expr::This)
+                    }
+                    PropKey::Pat(Pat::Ident(key)) if prop.computed => {
+                        in_place = true;
+                        blocks.push(Block::from_value(Expr::Ident(key)));
+                        PropKey::Expr(Expr::This)
+                    }
+                    key => key,
+                };
+                Prop { key, ..prop }
+            })
+            .collect();
+
+        let (lines, values) = self.in_order(blocks, &[]);
+        let mut values = values.into_iter();
+        let mut next = || values.next().expect("One value per slot");
+
+        let super_class = super_class.map(|_| Box::new(next()));
+        let props = props
+            .into_iter()
+            .map(|prop| {
+                let key = match prop.key {
+                    PropKey::Expr(_) if prop.computed => PropKey::Expr(next()),
+                    key => key,
+                };
+                self.method(Prop { key, ..prop })
+            })
+            .collect();
+
+        let class = Class {
+            id,
+            super_class,
+            body: ClassBody(props),
+        };
+        (lines, class)
+    }
+    // Blockifies an operand, or keeps it in place as `as_expr` does when its
+    // lines can't be hoisted.
+    fn operand<'a>(&mut self, expr: Expr<'a>, in_place: bool) -> Block<'a>
+    where
+        'b: 'a,
+    {
+        if in_place {
+            Block::from_value(self.as_expr(expr))
+        } else {
+            self.blockify(Blockable::Expr(expr))
+        }
+    }
+    // Whether `expr` is sure to have the same value after `later` runs.
+    // `super` counts as one, since it can't be stored in a temp anyway. A
+    // plain variable is, unless `later` assigns to it, can pause at an
+    // `await` or `yield`, or could run code that assigns to it from a
+    // closure. That last one can't happen to a temp, whose fresh name nothing
+    // else mentions, or a local that no closure captures.
+    fn is_stable(&self, expr: &Expr, later: &[Line]) -> bool {
+        later.iter().all(|line| self.is_stable_in(expr, line))
+    }
+    fn is_stable_in(&self, expr: &Expr, line: &Line) -> bool {
+        match expr {
+            Expr::Lit(Lit::Template(TemplateLit { expressions, .. })) => expressions.is_empty(),
+            Expr::Lit(_) | Expr::This | Expr::Super | Expr::Func(_) | Expr::ArrowFunc(_) => true,
+            Expr::Ident(Ident { name }) => {
+                let mut writes = WritesTo::new(name);
+                let mut suspends = Suspends::default();
+                match line {
+                    Line::Part(part) => {
+                        writes.visit_part(part);
+                        suspends.visit_part(part);
+                    }
+                    Line::Variable(var, init) => {
+                        writes.found |= var == name;
+                        if let Some(init) = init {
+                            writes.visit_expr(init);
+                            suspends.visit_expr(init);
+                        }
+                    }
+                }
+                let local = self.temps.contains(&name[..])
+                    || self
+                        .locals
+                        .last()
+                        .is_some_and(|locals| locals.contains(&name[..]));
+                !writes.found && !suspends.found && (local || !self.runs_code(line))
+            }
+            _ => false,
+        }
+    }
+    // Whether a line could run code the program wrote, as `Purity::runs_code`
+    // says. Functions it defines don't run, but a class's heritage and
+    // computed keys, destructuring and loops over iterators do.
+    fn runs_code(&self, line: &Line) -> bool {
+        struct Runs<'p> {
+            purity: &'p Purity,
+            found: bool,
+        }
+        impl<'a, 'p> Visit<'a> for Runs<'p> {
+            fn visit_stmt(&mut self, stmt: &Stmt<'a>) {
+                self.found |= matches!(stmt, Stmt::ForIn(_) | Stmt::ForOf(_) | Stmt::With(_));
+                walk_stmt(self, stmt)
+            }
+            fn visit_part(&mut self, part: &ProgramPart<'a>) {
+                match part {
+                    ProgramPart::Decl(Decl::Class(class)) => {
+                        self.found |= class.super_class.is_some()
+                            || class.body.0.iter().any(|prop| prop.computed)
+                    }
+                    part => walk_part(self, part),
+                }
+            }
+            fn visit_expr(&mut self, expr: &Expr<'a>) {
+                self.found |= self.purity.runs_code(expr);
+            }
+            fn visit_pat(&mut self, pat: &Pat<'a>) {
+                self.found |= !matches!(pat, Pat::Ident(_));
+            }
+            fn visit_func(&mut self, _func: &Func<'a>) {}
+        }
+        let mut runs = Runs {
+            purity: &self.purity,
+            found: false,
+        };
+        match line {
+            Line::Part(part) => runs.visit_part(part),
+            Line::Variable(_, init) => {
+                if let Some(init) = init {
+                    runs.visit_expr(init)
+                }
+            }
+        }
+        runs.found
+    }
+    // Stores `expr` in a temp first if running `later` could change its value.
+    fn spill<'a>(
+        &mut self,
+        expr: Expr<'a>,
+        lines: &mut Vec<Line<'a>>,
+        later: &[Line<'a>],
+    ) -> Expr<'a>
+    where
+        'b: 'a,
+    {
+        if later.is_empty() || self.is_stable(&expr, later) {
+            return expr;
+        }
+        let my_var = &self.temp(Some(&expr), "value");
+        lines.extend(self.declare(my_var, Some(expr), false));
+        get_id(my_var)
+    }
+    // Lines and values for operands that are evaluated left to right. Each
+    // value is spilled if a later operand's lines, or `later`, could change it.
+    fn in_order<'a>(
+        &mut self,
+        blocks: Vec<Block<'a>>,
+        later: &[Line<'a>],
+    ) -> (Vec<Line<'a>>, Vec<Expr<'a>>)
+    where
+        'b: 'a,
+    {
+        let mut values = vec![];
+        let mut line_lists = vec![];
+        for mut block in blocks {
+            values.push(block.expr());
+            line_lists.push(block.lines());
+        }
+
+        let mut lines = vec![];
+        let mut exprs = vec![];
+        let count = values.len();
+        for (idx, value) in values.into_iter().enumerate() {
+            lines.append(&mut line_lists[idx]);
+            let rest: Vec<&Line<'a>> = line_lists[idx + 1..count]
+                .iter()
+                .flatten()
+                .chain(later)
+                .collect();
+            let value =
+                if rest.is_empty() || rest.iter().all(|line| self.is_stable_in(&value, line)) {
+                    value
+                } else {
+                    let my_var = &self.temp(Some(&value), "value");
+                    match value {
+                        // Spreading runs the iterator, so it's done up front
+                        // into an array: `let t = [...xs]; f(...t, ...)`.
+                        Expr::Spread(_) => {
+                            let spread = Expr::Array(vec![Some(value)]);
+                            lines.extend(self.declare(my_var, Some(spread), false));
+                            Expr::Spread(Box::new(get_id(my_var)))
+                        }
+                        value => {
+                            lines.extend(self.declare(my_var, Some(value), false));
+                            get_id(my_var)
+                        }
+                    }
+                };
+            exprs.push(value);
+        }
+        (lines, exprs)
+    }
+    // Only a callee written as `obj.method` or `eval` gets `obj` as `this` or
+    // runs as a direct eval. A method's object is kept apart so it can be
+    // spilled on its own, unless it's `super`, which stays where it is like
+    // the callee of `super(...)`.
+    fn callee<'a>(&mut self, callee: Expr<'a>) -> (Vec<Block<'a>>, Callee<'a>)
+    where
+        'b: 'a,
+    {
+        match callee {
+            Expr::Member(MemberExpr {
+                object,
+                property,
+                computed,
+            }) => {
+                let (blocks, property) = self.member_parts(*object, *property, computed);
+                (blocks, Callee::Method(property, computed))
+            }
+            // Already unbound, as `unbind` writes it. Blockifying the `0` would
+            // leave a stray `0;` behind on every run.
+            Expr::Sequence(mut seq)
+                if seq.len() == 2
+                    && matches!(seq[0], Expr::Lit(Lit::Number(_)))
+                    && keeps_binding(&seq[1]) =>
+            {
+                let callee = seq.pop().expect("Sequence has a callee");
+                (vec![self.blockify(Blockable::Expr(callee))], Callee::Value)
+            }
+            callee => {
+                let kind = if matches!(callee, Expr::Ident(_)) {
+                    Callee::Reference
+                } else {
+                    Callee::Value
+                };
+                (vec![self.blockify(Blockable::Expr(callee))], kind)
+            }
+        }
+    }
+    // Blocks for a member expression's object and, when computed, its
+    // property. A plain `.name` is returned as is; see `member`.
+    fn member_parts<'a>(
+        &mut self,
+        object: Expr<'a>,
+        property: Expr<'a>,
+        computed: bool,
+    ) -> (Vec<Block<'a>>, Option<Expr<'a>>)
+    where
+        'b: 'a,
+    {
+        let mut blocks = vec![self.blockify(Blockable::Expr(object))];
+        if computed {
+            blocks.push(self.blockify(Blockable::Expr(property)));
+            (blocks, None)
+        } else {
+            (blocks, Some(property))
+        }
+    }
+    // A name for a temp that will hold `value`. Names the program uses are
+    // never handed out, across the whole program rather than per scope, which
+    // is stricter than needed but means a temp can't shadow or capture
+    // anything. Reserved words and common globals are skipped too.
+    fn temp(&mut self, value: Option<&Expr>, purpose: &str) -> String {
+        match value {
+            Some(value) => self.name(Some(value), value, purpose),
+            None => self.name(None, &(), purpose),
+        }
+    }
+    // A name for a temp that doesn't start out with a value, made for
+    // `context`, like the test of the loop a first-iteration flag is for.
+    fn temp_for(&mut self, context: &dyn Debug, purpose: &str) -> String {
+        self.name(None, context, purpose)
+    }
+    fn name(&mut self, value: Option<&Expr>, context: &dyn Debug, purpose: &str) -> String {
+        let mut attempt = 0;
+        loop {
+            let name = self.namer.propose(value, context, purpose, attempt);
+            if names::is_identifier(&name)
+                && !names::is_unavailable(&name)
+                && self.taken.insert(name.clone())
+            {
+                self.temps.insert(name.clone());
+                self.purity.declare(&name);
+                return name;
+            }
+            attempt += 1;
+        }
+    }
+    // Declares a temp as the `temps` option says. `reassigned` is set when
+    // lines after this one assign to it again. A `var` temp is declared by
+    // `scoped`, so only its initializer is left here.
+    fn declare<'a>(
+        &mut self,
+        name: &str,
+        init: Option<Expr<'a>>,
+        reassigned: bool,
+    ) -> Option<Line<'a>>
+    where
+        'b: 'a,
+    {
+        match self.options.temps {
+            Temps::Const if !reassigned && init.is_some() => {
+                Some(Line::Variable(Cow::Owned(name.to_string()), init))
+            }
+            Temps::Const | Temps::Let => Some(let_line(name, init)),
+            Temps::Var => {
+                self.hoisted
+                    .last_mut()
+                    .expect("Temp outside of a scope")
+                    .push(name.to_string());
+                init.map(|init| Line::Part(assign_line(name, init)))
+            }
+        }
+    }
+    // Blockifies the body of a function or program, declaring its `var`
+    // temps at the top, after any directives.
+    fn scoped<'a>(&mut self, blockable: Blockable<'a>) -> Block<'a>
+    where
+        'b: 'a,
+    {
+        self.hoisted.push(vec![]);
+        let block = self.blockify(blockable);
+        self.close_scope(block)
+    }
+    // Declares the `var` temps of the scope pushed onto `hoisted` at the top
+    // of `block`.
+    fn close_scope<'a>(&mut self, mut block: Block<'a>) -> Block<'a>
+    where
+        'b: 'a,
+    {
+        let names = self.hoisted.pop().expect("Scope was pushed");
+        if !names.is_empty() {
+            let decls = names
+                .into_iter()
+                .map(|name| VarDecl {
+                    id: Pat::Ident(get_id_pat(&name)),
+                    init: None,
+                })
+                .collect();
+            let idx = block
+                .action_lines
+                .iter()
+                .take_while(|line| matches!(line, Line::Part(ProgramPart::Dir(_))))
+                .count();
+            let decl = Line::Part(ProgramPart::Decl(Decl::Var(VarKind::Var, decls)));
+            block.action_lines.insert(idx, decl);
+        }
+        block
+    }
+    // `let first = true`, for a loop that skips something on its first pass.
+    fn flag_init<'a>(&mut self, name: &str) -> LoopInit<'a>
+    where
+        'b: 'a,
+    {
+        let init = Expr::Lit(Lit::Boolean(true));
+        match self.declare(name, Some(init), true) {
+            Some(Line::Part(ProgramPart::Decl(Decl::Var(kind, decls)))) => {
+                LoopInit::Variable(kind, decls)
+            }
+            Some(Line::Part(ProgramPart::Stmt(Stmt::Expr(expr)))) => LoopInit::Expr(expr),
+            line => unreachable!("Not a loop init: {:?}", line),
+        }
+    }
+    // The temp that a for-in or for-of loop binds in place of its target.
+    fn loop_temp<'a>(&mut self, name: &str) -> LoopLeft<'a>
+    where
+        'b: 'a,
+    {
+        let kind = match self.options.temps {
+            Temps::Const => VarKind::Const,
+            Temps::Let => VarKind::Let,
+            Temps::Var => {
+                self.declare(name, None, true);
+                return LoopLeft::Expr(get_id(name));
+            }
+        };
+        LoopLeft::Variable(
+            kind,
+            VarDecl {
+                id: Pat::Ident(get_id_pat(name)),
+                init: None,
+            },
+        )
+    }
+    // The iterable of a for-in or for-of loop is evaluated once, before the
+    // first iteration. Returns its lines, then the lowered loop parts.
+    #[allow(clippy::type_complexity)]
+    fn for_each<'a>(
+        &mut self,
+        left: LoopLeft<'a>,
+        right: Expr<'a>,
+        body: Stmt<'a>,
+    ) -> (Vec<Line<'a>>, LoopLeft<'a>, Expr<'a>, Box<Stmt<'a>>)
+    where
+        'b: 'a,
+    {
+        let mut r_block = self.blockify(Blockable::Expr(right));
+        let right = r_block.expr();
+        let lines = r_block.lines();
+
+        let (left, mut body_lines) = self.loop_left(left);
+        body_lines.append(&mut self.blockify(Blockable::Stmt(body)).lines());
+
+        (lines, left, right, wrap(Block::from_lines(body_lines)))
+    }
+    // A loop target with lines of its own is bound through a temp instead:
+    // `for (const t of xs) { ...lines; target = t; ...body }`. The returned
+    // lines go at the top of the body, so they still run once per iteration.
+    fn loop_left<'a>(&mut self, left: LoopLeft<'a>) -> (LoopLeft<'a>, Vec<Line<'a>>)
+    where
+        'b: 'a,
+    {
+        let (binding, pat) = match left {
+            LoopLeft::Variable(kind, VarDecl { id, init }) => {
+                let (pre, pat, post) = self.pattern(id, Some(kind));
+                if pre.is_empty() && post.is_empty() {
+                    return (LoopLeft::Variable(kind, VarDecl { id: pat, init }), vec![]);
+                }
+                (Some((kind, pre, post)), pat)
+            }
+            LoopLeft::Pat(pat) => (None, pat),
+            LoopLeft::Expr(expr @ Expr::Array(_)) | LoopLeft::Expr(expr @ Expr::Obj(_)) => {
+                match expr_pat(expr.clone()) {
+                    Some(pat) => (None, pat),
+                    None => return (LoopLeft::Expr(expr), vec![]),
+                }
+            }
+            LoopLeft::Expr(target @ Expr::Member(_)) => {
+                let mut t_block = self.blockify(Blockable::Expr(target));
+                let target = t_block.expr();
+                if t_block.action_lines.is_empty() {
+                    return (LoopLeft::Expr(target), vec![]);
+                }
+                let my_var = &self.temp_for(&(&t_block.action_lines, &target), "item");
+                let mut lines = t_block.action_lines;
+                lines.push(Line::Part(ProgramPart::Stmt(Stmt::Expr(Expr::Assign(
+                    AssignExpr {
+                        operator: AssignOp::Equal,
+                        left: AssignLeft::Expr(Box::new(target)),
+                        right: Box::new(get_id(my_var)),
+                    },
+                )))));
+                return (self.loop_temp(my_var), lines);
+            }
+            left => return (left, vec![]),
+        };
+
+        let (kind, mut pre, pat, mut post) = match binding {
+            Some((kind, pre, post)) => (Some(kind), pre, pat, post),
+            None => {
+                let (pre, pat, post) = self.pattern(pat, None);
+                if pre.is_empty() && post.is_empty() {
+                    return (LoopLeft::Pat(pat), vec![]);
+                }
+                (None, pre, pat, post)
+            }
+        };
+        let my_var = &self.temp_for(&pat, "item");
+        pre.push(bind_line(pat, get_id(my_var), kind));
+        pre.append(&mut post);
+        (self.loop_temp(my_var), pre)
+    }
+    // The right-hand side of an assignment or initializer. With
+    // `split_assignments`, an inner assignment becomes a line of its own and
+    // only the value it assigned is left: `a = b = c` is `b = c; a = b;`.
+    fn assigned<'a>(&mut self, right: Expr<'a>) -> Block<'a>
+    where
+        'b: 'a,
+    {
+        let inner = match right {
+            Expr::Assign(inner) if self.options.split_assignments => inner,
+            right => return self.blockify(Blockable::Expr(right)),
+        };
+        let mut block = self.assign(inner, true);
+        let value = match block.value() {
+            Some(Expr::Assign(assign)) => assign,
+            value => {
+                block.value = value;
+                return block;
+            }
+        };
+
+        let value = match (&value.operator, &value.left) {
+            // A variable reads back what was just stored in it.
+            (_, AssignLeft::Expr(left)) if matches!(**left, Expr::Ident(_)) => {
+                let read = (**left).clone();
+                block
+                    .action_lines
+                    .push(Line::Part(ProgramPart::Stmt(Stmt::Expr(Expr::Assign(
+                        value,
+                    )))));
+                read
+            }
+            // A property might not (a setter, or a frozen object), so the
+            // assigned value is used directly, through a temp if needed.
+            (AssignOp::Equal, _) => {
+                let AssignExpr { left, right, .. } = value;
+                let line = |right| {
+                    Line::Part(ProgramPart::Stmt(Stmt::Expr(Expr::Assign(AssignExpr {
+                        operator: AssignOp::Equal,
+                        left: left.clone(),
+                        right: Box::new(right),
+                    }))))
+                };
+                let reusable = match &*right {
+                    Expr::Func(_) | Expr::ArrowFunc(_) => false,
+                    right => self.is_stable_in(right, &line(right.clone())),
+                };
+                if reusable {
+                    block.action_lines.push(line((*right).clone()));
+                    *right
+                } else {
+                    let my_var = &self.temp(Some(&*right), "value");
+                    let temp = self.declare(my_var, Some(*right), false);
+                    block.action_lines.extend(temp);
+                    block.action_lines.push(line(get_id(my_var)));
+                    get_id(my_var)
+                }
+            }
+            // A compound assignment to a property can't be split without
+            // reading the property back.
+            _ => Expr::Assign(value),
+        };
+        block.value = Some(value);
+        block
+    }
+    // `a && b` becomes `t = a; if (t) { t = b; }`, with `!t` for `||`.
+    fn logical<'a>(&mut self, logical: LogicalExpr<'a>) -> Block<'a>
+    where
+        'b: 'a,
+    {
+        let LogicalExpr {
+            operator,
+            left,
+            right,
+        } = logical;
+        let lower = self.options.lowerings.logicals;
+        let mut l_block = self.blockify(Blockable::Expr(*left));
+        let mut r_block = if lower {
+            self.blockify(Blockable::Expr(*right))
+        } else {
+            Block::from_value(self.as_expr(*right))
+        };
+
+        // Nothing to short-circuit but reads, so it reads best as it is.
+        if !lower || !self.must_lower(&r_block) {
+            let left = Box::new(l_block.expr());
+            let right = Box::new(r_block.expr());
+            return Block {
+                action_lines: l_block.lines(),
+                value: Some(Expr::Logical(LogicalExpr {
+                    operator,
+                    left,
+                    right,
+                })),
+            };
+        }
+
+        let my_var = &self.temp(l_block.value.as_ref(), "either");
+
+        let var_line = self.declare(my_var, l_block.value(), true);
+
+        let id = get_id(my_var);
+
+        let test = match operator {
+            LogicalOp::And => id,
+            LogicalOp::Or => Expr::Unary(UnaryExpr {
+                operator: UnaryOp::Not,
+                prefix: true,
+                argument: Box::new(id),
+            }),
+        };
+
+        let ident = get_id(my_var);
+
+        let mod_line = ProgramPart::Stmt(Stmt::Expr(Expr::Assign(AssignExpr {
+            operator: AssignOp::Equal,
+            left: AssignLeft::Expr(Box::new(get_id(my_var))),
+            right: Box::new(r_block.expr()),
+        })));
+
+        let BlockStmt(mut parts) = r_block.block();
+        parts.push(mod_line);
+
+        let c_body = Stmt::Block(BlockStmt(parts));
+
+        let if_st = ProgramPart::Stmt(Stmt::If(IfStmt {
+            test,
+            consequent: Box::new(c_body),
+            alternate: None,
+        }));
+
+        let mut lines = l_block.lines();
+
+        lines.extend(var_line);
+
+        lines.push(Line::Part(if_st));
+
+        Block {
+            action_lines: lines,
+            value: Some(ident),
+        }
+    }
+    fn conditional<'a>(&mut self, conditional: ConditionalExpr<'a>) -> Block<'a>
+    where
+        'b: 'a,
+    {
+        let ConditionalExpr {
+            test,
+            consequent,
+            alternate,
+        } = conditional;
+        let lower = self.options.lowerings.conditionals;
+        let mut t_block = self.blockify(Blockable::Expr(*test));
+        let (mut c_block, mut a_block) = if lower {
+            (
+                self.blockify(Blockable::Expr(*consequent)),
+                self.blockify(Blockable::Expr(*alternate)),
+            )
+        } else {
+            (
+                Block::from_value(self.as_expr(*consequent)),
+                Block::from_value(self.as_expr(*alternate)),
+            )
+        };
+
+        // Likewise when neither arm does anything.
+        if !lower || !self.must_lower(&c_block) && !self.must_lower(&a_block) {
+            let consequent = Box::new(c_block.expr());
+            let alternate = Box::new(a_block.expr());
+            return Block {
+                value: Some(Expr::Conditional(ConditionalExpr {
+                    test: Box::new(t_block.expr()),
+                    consequent,
+                    alternate,
+                })),
+                action_lines: t_block.lines(),
+            };
+        }
+
+        let my_var = &self.temp_for(&(&t_block, &c_block, &a_block), "choice");
+
+        let var_line = self.declare(my_var, None, true);
+
+        // Each arm runs its own lines, then stores its value in the temp.
+        let c_line = assign_line(my_var, c_block.expr());
+        let a_line = assign_line(my_var, a_block.expr());
+
+        let BlockStmt(mut c_parts) = c_block.block();
+        c_parts.push(c_line);
+        let BlockStmt(mut a_parts) = a_block.block();
+        a_parts.push(a_line);
+
+        let if_st = ProgramPart::Stmt(Stmt::If(IfStmt {
+            test: t_block.expr(),
+            consequent: Box::new(Stmt::Block(BlockStmt(c_parts))),
+            alternate: Some(Box::new(Stmt::Block(BlockStmt(a_parts)))),
+        }));
+
+        let mut lines = t_block.lines();
+
+        lines.extend(var_line);
+
+        lines.push(Line::Part(if_st));
+
+        Block {
+            action_lines: lines,
+            value: Some(get_id(my_var)),
+        }
+    }
+    // `value_used` is false when the assignment is a bare statement.
+    fn assign<'a>(&mut self, assign: AssignExpr<'a>, value_used: bool) -> Block<'a>
+    where
+        'b: 'a,
+    {
+        let AssignExpr {
+            operator,
+            left,
+            right,
+        } = assign;
+
+        // A plain `a = b` can still have `b`'s lines run first, since there's
+        // nothing on the left to evaluate before them.
+        if !self.options.lowerings.assignments {
+            let plain = matches!(operator, AssignOp::Equal)
+                && matches!(&left, AssignLeft::Expr(target) if matches!(**target, Expr::Ident(_)));
+            let mut r_block = if plain {
+                self.blockify(Blockable::Expr(*right))
+            } else {
+                Block::from_value(self.as_expr(*right))
+            };
+            let right = Box::new(r_block.expr());
+            return Block {
+                action_lines: r_block.lines(),
+                value: Some(Expr::Assign(AssignExpr {
+                    operator,
+                    left,
+                    right,
+                })),
+            };
+        }
+
+        let target = match left {
+            AssignLeft::Pat(pat) => return self.destructure(pat, *right, value_used),
+            AssignLeft::Expr(target) => *target,
+        };
+
+        // The target's object and key are evaluated before the right-hand side.
+        let (t_blocks, member_target) = match target {
+            Expr::Member(MemberExpr {
+                object,
+                property,
+                computed,
+            }) => {
+                let (blocks, property) = self.member_parts(*object, *property, computed);
+                (blocks, Some((property, computed)))
+            }
+            target => (vec![Block::from_value(target)], None),
+        };
+
+        let mut r_block = self.assigned(*right);
+        let r_value = r_block.expr();
+        let r_lines = r_block.lines();
+
+        let (mut lines, target) = match member_target {
+            Some((property, computed)) => {
+                let (lines, values) = self.in_order(t_blocks, &r_lines);
+                (lines, member(values, property, computed))
+            }
+            // A plain variable is a reference here, so it's never spilled.
+            None => (vec![], t_blocks.into_iter().next().unwrap().expr()),
+        };
+
+        let (operator, right) = match binary_op(operator) {
+            // A compound assignment reads the target before the right-hand
+            // side runs, so that read has to be hoisted too.
+            Some(bin_op) if !r_lines.is_empty() => {
+                let old = self.spill(target.clone(), &mut lines, &r_lines);
+                let right = Expr::Binary(BinaryExpr {
+                    operator: bin_op,
+                    left: Box::new(old),
+                    right: Box::new(r_value),
+                });
+                (AssignOp::Equal, right)
+            }
+            _ => (operator, r_value),
+        };
+        lines.extend(r_lines);
+
+        Block {
+            action_lines: lines,
+            value: Some(Expr::Assign(AssignExpr {
+                operator,
+                left: AssignLeft::Expr(Box::new(target)),
+                right: Box::new(right),
+            })),
+        }
+    }
+    fn destructure<'a>(&mut self, pat: Pat<'a>, right: Expr<'a>, value_used: bool) -> Block<'a>
+    where
+        'b: 'a,
+    {
+        let mut r_block = self.assigned(right);
+        let (mut pre, pat, mut post) = self.pattern(pat, None);
+
+        let r_value = r_block.expr();
+        let mut lines = r_block.lines();
+        let mut r_value = self.spill(r_value, &mut lines, &pre);
+        lines.append(&mut pre);
+
+        let value = if post.is_empty() {
+            Expr::Assign(AssignExpr {
+                operator: AssignOp::Equal,
+                left: AssignLeft::Pat(pat),
+                right: Box::new(r_value),
+            })
+        } else {
+            // The source is the assignment's value, and it has to outlive
+            // the lines that finish the destructuring.
+            if value_used {
+                r_value = self.spill(r_value, &mut lines, &post);
+            }
+            lines.push(Line::Part(ProgramPart::Stmt(Stmt::Expr(Expr::Assign(
+                AssignExpr {
+                    operator: AssignOp::Equal,
+                    left: AssignLeft::Pat(pat),
+                    right: Box::new(r_value.clone()),
+                },
+            )))));
+            lines.append(&mut post);
+            r_value
+        };
+
+        Block {
+            action_lines: lines,
+            value: if value_used || post.is_empty() {
+                Some(value)
+            } else {
+                None
+            },
+        }
+    }
+    // Destructuring works through an object pattern one property at a time:
+    // it evaluates the key, reads the property and works out its default
+    // before moving on. So a pattern whose parts hoist lines is split at the
+    // first part that does. The source goes in a temp, and the parts before
+    // that one are destructured from it as they were. That part is then
+    // read, defaulted and bound in lines of its own, and the parts after it
+    // are handled the same way.
+    //
+    // An array pattern is left as it is when it would hoist lines, since its
+    // elements share one iterator, and splitting it would change when
+    // `next()` and `return()` are called. So is an object pattern when a
+    // rest element comes after the split, since it would then take the
+    // properties read before it, and when `splits` finds the order of its
+    // key conversions would change.
+    //
+    // Returns the lines to run before destructuring (declaring the temps it
+    // needs), the new pattern, and the lines to run after. With `binding`
+    // set, targets are declared with that kind instead of assigned.
+    fn pattern<'a>(
+        &mut self,
+        pat: Pat<'a>,
+        binding: Option<VarKind>,
+    ) -> (Vec<Line<'a>>, Pat<'a>, Vec<Line<'a>>)
+    where
+        'b: 'a,
+    {
+        if !self.options.lowerings.assignments {
+            return (vec![], pat, vec![]);
+        }
+        if !self.pat_hoists(&pat) {
+            return (vec![], self.pat_in_place(pat), vec![]);
+        }
+        match pat {
+            Pat::Assign(AssignPat { left, right }) => {
+                let (mut l_pre, left, mut l_post) = self.pattern(*left, binding);
+                let mut r_block = self.blockify(Blockable::Expr(*right));
+                if r_block.action_lines.is_empty() && l_pre.is_empty() && l_post.is_empty() {
+                    let right = Box::new(r_block.expr());
+                    return (
+                        vec![],
+                        Pat::Assign(AssignPat {
+                            left: Box::new(left),
+                            right,
+                        }),
+                        vec![],
+                    );
+                }
+
+                let my_var = &self.temp_for(&(&left, &r_block), "element");
+                let mut pre = vec![];
+                let mut post = vec![];
+                // A `const` binding can't be reassigned with the default.
+                let value_var = match binding {
+                    None => {
+                        pre.extend(self.declare(my_var, None, true));
+                        my_var.clone()
+                    }
+                    Some(VarKind::Const) => {
+                        let value = get_id(my_var);
+                        let value_var = self.temp(Some(&value), "element");
+                        post.extend(self.declare(&value_var, Some(value), true));
+                        value_var
+                    }
+                    Some(_) => my_var.clone(),
+                };
+                let value_var = &value_var;
+
+                post.push(default_line(value_var, r_block));
+
+                post.append(&mut l_pre);
+                post.push(bind_line(left, get_id(value_var), binding));
+                post.append(&mut l_post);
+
+                (pre, Pat::Ident(get_id_pat(my_var)), post)
+            }
+            Pat::Obj(parts) if self.splits(&parts) => {
+                let source = &self.temp_for(&parts, "source");
+                let pre = match binding {
+                    None => self.declare(source, None, true).into_iter().collect(),
+                    Some(_) => vec![],
+                };
+                let post = self.properties(parts, source, binding);
+                (pre, Pat::Ident(get_id_pat(source)), post)
+            }
+            Pat::RestElement(rest) => {
+                let (pre, rest, post) = self.pattern(*rest, binding);
+                (pre, Pat::RestElement(Box::new(rest)), post)
+            }
+            pat => (vec![], self.pat_in_place(pat), vec![]),
+        }
+    }
+    // Whether an object pattern can be split as `pattern` says. From the
+    // first part that hoists lines on, there's no rest element, and each
+    // property is one `property` can read on its own. The first part can't
+    // hoist lines from its key or target, which would run before the source
+    // is checked for `null`. Nor can a target hoist lines after a computed
+    // key that isn't a literal, which would run before the key is converted
+    // to a string.
+    fn splits(&self, parts: &[ObjPatPart]) -> bool {
+        let Some(split) = parts.iter().position(|part| self.part_hoists(part)) else {
+            return false;
+        };
+        parts[split..].iter().enumerate().all(|(idx, part)| {
+            let ObjPatPart::Assign(prop) = part else {
+                return false;
+            };
+            let key_hoists = match &prop.key {
+                PropKey::Expr(key) if prop.computed => self.hoists(key),
+                PropKey::Pat(Pat::Ident(_)) | PropKey::Lit(_) => false,
+                _ => return false,
+            };
+            let converts =
+                prop.computed && !matches!(&prop.key, PropKey::Expr(key) if !converts(key));
+            let target_hoists = match &prop.value {
+                PropValue::None => !matches!(prop.key, PropKey::Pat(Pat::Ident(_))),
+                PropValue::Pat(_) => false,
+                PropValue::Expr(target) => match member_target(target) {
+                    Some(member) => self.hoists(member),
+                    None => return false,
+                },
+            };
+            let first = split + idx == 0;
+            !(target_hoists && converts) && !(first && (key_hoists || target_hoists))
+        })
+    }
+    // Lines that destructure `parts` from the temp `source`, splitting them
+    // up for `pattern`.
+    fn properties<'a>(
+        &mut self,
+        mut parts: Vec<ObjPatPart<'a>>,
+        source: &str,
+        binding: Option<VarKind>,
+    ) -> Vec<Line<'a>>
+    where
+        'b: 'a,
+    {
+        let mut lines = vec![];
+        while let Some(split) = parts.iter().position(|part| self.part_hoists(part)) {
+            let rest = parts.split_off(split + 1);
+            let part = parts.pop().expect("Split at a part");
+            if !parts.is_empty() {
+                let pat = self.pat_in_place(Pat::Obj(parts));
+                lines.push(bind_line(pat, get_id(source), binding));
+            }
+            if let ObjPatPart::Assign(prop) = part {
+                lines.append(&mut self.property(prop, source, binding));
+            }
+            parts = rest;
+        }
+        if !parts.is_empty() {
+            let pat = self.pat_in_place(Pat::Obj(parts));
+            lines.push(bind_line(pat, get_id(source), binding));
+        }
+        lines
+    }
+    // Lines that read one property from `source` and bind it: the key, then
+    // the target's object and key, then the read, the default and the
+    // binding, in the order destructuring does them.
+    fn property<'a>(
+        &mut self,
+        prop: Prop<'a>,
+        source: &str,
+        binding: Option<VarKind>,
+    ) -> Vec<Line<'a>>
+    where
+        'b: 'a,
+    {
+        let mut lines = vec![];
+        let (key, computed) = match prop.key {
+            PropKey::Expr(key) if prop.computed => {
+                let mut k_block = self.blockify(Blockable::Expr(key));
+                let key = k_block.expr();
+                lines.append(&mut k_block.lines());
+                (key, true)
+            }
+            PropKey::Pat(Pat::Ident(key)) => (Expr::Ident(key), prop.computed),
+            PropKey::Lit(key) => (Expr::Lit(key), true),
+            key => unreachable!("Not a key that splits: {:?}", key),
+        };
+        let value_hoists = match &prop.value {
+            PropValue::Pat(value) => self.pat_hoists(value),
+            PropValue::Expr(target) => self.hoists(target),
+            PropValue::None => false,
+        };
+        if !value_hoists {
+            // Only the key hoisted lines, so once they've run the property
+            // is destructured on its own.
+            let value = match prop.value {
+                PropValue::Pat(value) => PropValue::Pat(self.pat_in_place(value)),
+                PropValue::Expr(target) => PropValue::Expr(self.as_expr(target)),
+                PropValue::None => PropValue::None,
+            };
+            let prop = Prop {
+                key: PropKey::Expr(key),
+                value,
+                computed: true,
+                short_hand: false,
+                ..prop
+            };
+            let pat = Pat::Obj(vec![ObjPatPart::Assign(prop)]);
+            lines.push(bind_line(pat, get_id(source), binding));
+            return lines;
+        }
+        let read = Expr::Member(MemberExpr {
+            object: Box::new(get_id(source)),
+            property: Box::new(key.clone()),
+            computed,
+        });
+        match prop.value {
+            PropValue::None => match key {
+                Expr::Ident(key) => lines.push(bind_line(Pat::Ident(key), read, binding)),
+                key => unreachable!("Shorthand without a name: {:?}", key),
+            },
+            PropValue::Pat(Pat::Assign(AssignPat { left, right })) => {
+                let my_var = &self.temp(Some(&read), "element");
+                lines.extend(self.declare(my_var, Some(read), true));
+                let d_block = self.blockify(Blockable::Expr(*right));
+                lines.push(default_line(my_var, d_block));
+                lines.append(&mut self.bind(*left, get_id(my_var), binding));
+            }
+            PropValue::Pat(value) => lines.append(&mut self.bind(value, read, binding)),
+            PropValue::Expr(target) => {
+                let (target, default) = match target {
+                    Expr::Assign(AssignExpr {
+                        operator: AssignOp::Equal,
+                        left: AssignLeft::Expr(target),
+                        right,
+                    }) => (*target, Some(*right)),
+                    target => (target, None),
+                };
+                let (object, property, computed) = match target {
+                    Expr::Member(MemberExpr {
+                        object,
+                        property,
+                        computed,
+                    }) => (*object, *property, computed),
+                    target => unreachable!("Not a target that splits: {:?}", target),
+                };
+                let (t_blocks, property) = self.member_parts(object, property, computed);
+                let mut later = vec![];
+                let value = match default {
+                    // `o.x = source.a`, which reads after `o`, like destructuring.
+                    None => read,
+                    Some(default) => {
+                        let my_var = &self.temp(Some(&read), "element");
+                        later.extend(self.declare(my_var, Some(read), true));
+                        let d_block = self.blockify(Blockable::Expr(default));
+                        later.push(default_line(my_var, d_block));
+                        get_id(my_var)
+                    }
+                };
+                let (mut t_lines, values) = self.in_order(t_blocks, &later);
+                lines.append(&mut t_lines);
+                lines.append(&mut later);
+                let target = member(values, property, computed);
+                lines.push(Line::Part(ProgramPart::Stmt(Stmt::Expr(Expr::Assign(
+                    AssignExpr {
+                        operator: AssignOp::Equal,
+                        left: AssignLeft::Expr(Box::new(target)),
+                        right: Box::new(value),
+                    },
+                )))));
+            }
+        }
+        lines
+    }
+    // Lines that bind `pat` to `value`, as a declaration of kind `binding`
+    // or an assignment.
+    fn bind<'a>(&mut self, pat: Pat<'a>, value: Expr<'a>, binding: Option<VarKind>) -> Vec<Line<'a>>
+    where
+        'b: 'a,
+    {
+        let (mut lines, pat, mut post) = self.pattern(pat, binding);
+        lines.push(bind_line(pat, value, binding));
+        lines.append(&mut post);
+        lines
+    }
+    // Whether blockifying the keys, defaults and targets of a pattern would
+    // hoist lines out of them.
+    fn pat_hoists(&self, pat: &Pat) -> bool {
+        let mut hoists = PatHoists {
+            blockifier: self,
+            found: false,
+        };
+        hoists.visit_pat(pat);
+        hoists.found
+    }
+    fn part_hoists(&self, part: &ObjPatPart) -> bool {
+        let mut hoists = PatHoists {
+            blockifier: self,
+            found: false,
+        };
+        match part {
+            ObjPatPart::Assign(prop) => walk_prop(&mut hoists, prop),
+            ObjPatPart::Rest(rest) => hoists.visit_pat(rest),
+        }
+        hoists.found
+    }
+    // Blockifies the keys, defaults and targets of a pattern where they are,
+    // as `as_expr` does.
+    fn pat_in_place<'a>(&mut self, pat: Pat<'a>) -> Pat<'a>
+    where
+        'b: 'a,
+    {
+        match pat {
+            Pat::Obj(parts) => Pat::Obj(
+                parts
+                    .into_iter()
+                    .map(|part| match part {
+                        ObjPatPart::Assign(prop) => {
+                            let key = match prop.key {
+                                PropKey::Expr(key) if prop.computed => {
+                                    PropKey::Expr(self.as_expr(key))
+                                }
+                                key => key,
+                            };
+                            let value = match prop.value {
+                                PropValue::Pat(value) => PropValue::Pat(self.pat_in_place(value)),
+                                PropValue::Expr(target) => PropValue::Expr(self.as_expr(target)),
+                                PropValue::None => PropValue::None,
+                            };
+                            ObjPatPart::Assign(Prop { key, value, ..prop })
+                        }
+                        ObjPatPart::Rest(rest) => {
+                            ObjPatPart::Rest(Box::new(self.pat_in_place(*rest)))
+                        }
+                    })
+                    .collect(),
+            ),
+            Pat::Array(parts) => Pat::Array(
+                parts
+                    .into_iter()
+                    .map(|part| {
+                        part.map(|part| match part {
+                            ArrayPatPart::Pat(pat) => ArrayPatPart::Pat(self.pat_in_place(pat)),
+                            ArrayPatPart::Expr(target) => ArrayPatPart::Expr(self.as_expr(target)),
+                        })
+                    })
+                    .collect(),
+            ),
+            Pat::RestElement(rest) => Pat::RestElement(Box::new(self.pat_in_place(*rest))),
+            Pat::Assign(AssignPat { left, right }) => Pat::Assign(AssignPat {
+                left: Box::new(self.pat_in_place(*left)),
+                right: Box::new(self.as_expr(*right)),
+            }),
+            pat => pat,
+        }
+    }
+    // Blockifies an expression whose value is thrown away, so short-circuits
+    // and conditionals become plain `if` statements with no temp.
+    fn effects<'a>(&mut self, expr: Expr<'a>) -> Vec<Line<'a>>
+    where
+        'b: 'a,
+    {
+        match expr {
+            Expr::Logical(LogicalExpr {
+                left,
+                right,
+                operator,
+            }) if self.options.lowerings.logicals => {
+                let negate = operator == LogicalOp::Or;
+                self.guard(*left, negate, *right)
+            }
+            Expr::Conditional(ConditionalExpr {
+                test,
+                consequent,
+                alternate,
+            }) if self.options.lowerings.conditionals => {
+                let mut t_block = self.blockify(Blockable::Expr(*test));
+                let c_lines = self.effects(*consequent);
+                let a_lines = self.effects(*alternate);
+
+                let test = t_block.expr();
+                let mut lines = t_block.lines();
+
+                let stmt = match (c_lines.is_empty(), a_lines.is_empty()) {
+                    // The test is still evaluated for its side effects.
+                    (true, true) => {
+                        lines.extend(self.discard(test));
+                        return lines;
+                    }
+                    (false, true) => Stmt::If(IfStmt {
+                        test,
+                        consequent: wrap(Block::from_lines(c_lines)),
+                        alternate: None,
+                    }),
+                    (true, false) => Stmt::If(IfStmt {
+                        test: negate(test),
+                        consequent: wrap(Block::from_lines(a_lines)),
+                        alternate: None,
+                    }),
+                    (false, false) => Stmt::If(IfStmt {
+                        test,
+                        consequent: wrap(Block::from_lines(c_lines)),
+                        alternate: Some(wrap(Block::from_lines(a_lines))),
+                    }),
+                };
+                lines.push(Line::Part(ProgramPart::Stmt(stmt)));
+                lines
+            }
+            Expr::Sequence(seq) if self.options.lowerings.sequences => seq
+                .into_iter()
+                .flat_map(|expr| self.effects(expr))
+                .collect(),
+            Expr::Assign(assign) => self.assign(assign, false).lines(),
+            expr => {
+                let mut block = self.blockify(Blockable::Expr(expr));
+                let value = block.value();
+                let mut lines = block.lines();
+                lines.extend(value.and_then(|value| self.discard(value)));
+                lines
+            }
+        }
+    }
+    // The `else` of an `if`, kept as an `else if` when the nested `if` is all
+    // there is. Lines hoisted out of the nested test go in front of the whole
+    // statement, into `before`, when they only work out a temp from values
+    // that neither `test` nor `consequent` could change.
+    fn else_branch<'a>(
+        &self,
+        mut alternate: Block<'a>,
+        test: &Expr<'a>,
+        consequent: &Block<'a>,
+        before: &mut Vec<Line<'a>>,
+    ) -> Box<Stmt<'a>> {
+        let nested = alternate.value.is_none()
+            && matches!(
+                alternate.action_lines.last(),
+                Some(Line::Part(ProgramPart::Stmt(Stmt::If(_))))
+            );
+        let skipped = [Line::Part(ProgramPart::Stmt(Stmt::Expr(test.clone())))];
+        let early = |line: &Line<'a>| {
+            let value = match line {
+                Line::Variable(_, Some(value)) => value,
+                Line::Part(ProgramPart::Decl(Decl::Var(VarKind::Let, decls))) => match &decls[..] {
+                    [VarDecl {
+                        id: Pat::Ident(Ident { name }),
+                        init: Some(value),
+                    }] if self.temps.contains(&name[..]) => value,
+                    _ => return false,
+                },
+                Line::Part(ProgramPart::Stmt(Stmt::Expr(Expr::Assign(AssignExpr {
+                    operator: AssignOp::Equal,
+                    left: AssignLeft::Expr(left),
+                    right,
+                })))) => match &**left {
+                    Expr::Ident(Ident { name }) if self.temps.contains(&name[..]) => right,
+                    _ => return false,
+                },
+                _ => return false,
+            };
+            let mut names = Names::default();
+            names.visit_expr(value);
+            self.purity.is_dead(value)
+                && !reads_property(value)
+                && names.names.iter().all(|name| {
+                    let name = Expr::Ident(Ident::from(&name[..]));
+                    self.is_stable(&name, &skipped)
+                        && self.is_stable(&name, &consequent.action_lines)
+                })
+        };
+        let hoisted = alternate.action_lines.len().saturating_sub(1);
+        if nested && alternate.action_lines[..hoisted].iter().all(early) {
+            if let Some(Line::Part(ProgramPart::Stmt(nested))) = alternate.action_lines.pop() {
+                before.append(&mut alternate.action_lines);
+                return Box::new(nested);
+            }
+        }
+        wrap(alternate)
+    }
+    // Whether a block's lines declare something that would leak or clash if
+    // they were spliced into the enclosing block: a `let`, `const` or class,
+    // or a function, which is hoisted differently in a block. Temps have
+    // unique names, so theirs don't count.
+    fn is_scope(&self, lines: &[Line]) -> bool {
+        lines.iter().any(|line| match line {
+            Line::Part(ProgramPart::Decl(Decl::Func(_)))
+            | Line::Part(ProgramPart::Decl(Decl::Class(_))) => true,
+            Line::Part(ProgramPart::Decl(Decl::Var(VarKind::Let, decls)))
+            | Line::Part(ProgramPart::Decl(Decl::Var(VarKind::Const, decls))) => {
+                decls.iter().any(|decl| {
+                    let mut bindings = Bindings::default();
+                    bindings.visit_pat(&decl.id);
+                    bindings
+                        .names
+                        .iter()
+                        .any(|name| !self.temps.contains(&name[..]))
+                })
+            }
+            _ => false,
+        })
+    }
+    // Blockifies an expression that has to stay one, because the lowering
+    // that would make room for its lines is turned off. It's left as written
+    // if it would hoist any.
+    fn as_expr<'a>(&mut self, expr: Expr<'a>) -> Expr<'a>
+    where
+        'b: 'a,
+    {
+        if self.hoists(&expr) {
+            return expr;
+        }
+        let mut block = self.blockify(Blockable::Expr(expr));
+        assert!(
+            block.action_lines.is_empty(),
+            "Hoisted lines from an expression that shouldn't have any"
+        );
+        block.expr()
+    }
+    // Whether blockifying `expr` would hoist lines out of it, worked out
+    // without doing it. Lines only start at sequences, at short-circuits and
+    // conditionals that get lowered, and at chains and patterns that get
+    // split. Everything else only passes on the lines of its operands, and
+    // functions keep theirs to themselves.
+    fn hoists(&self, expr: &Expr) -> bool {
+        struct Hoists<'s> {
+            purity: &'s Purity,
+            options: Options,
+            found: bool,
+        }
+        impl<'a, 's> Visit<'a> for Hoists<'s> {
+            fn visit_expr(&mut self, expr: &Expr<'a>) {
+                let lowerings = self.options.lowerings;
+                match expr {
+                    Expr::ArrowFunc(_) => {}
+                    Expr::Sequence(_) if !lowerings.sequences => {}
+                    Expr::Sequence(seq) if seq.len() > 1 => self.found = true,
+                    Expr::Obj(_) if !lowerings.objects => {}
+                    Expr::Logical(LogicalExpr { left, right, .. }) => {
+                        self.visit_expr(left);
+                        if lowerings.logicals {
+                            self.found |= self.purity.has_side_effects(right);
+                            self.visit_expr(right);
+                        }
+                    }
+                    Expr::Conditional(ConditionalExpr {
+                        test,
+                        consequent,
+                        alternate,
+                    }) => {
+                        self.visit_expr(test);
+                        if lowerings.conditionals {
+                            for arm in [consequent, alternate] {
+                                self.found |= self.purity.has_side_effects(arm);
+                                self.visit_expr(arm);
+                            }
+                        }
+                    }
+                    Expr::Assign(AssignExpr {
+                        operator,
+                        left,
+                        right,
+                    }) if !lowerings.assignments => {
+                        let plain = matches!(operator, AssignOp::Equal)
+                            && matches!(left, AssignLeft::Expr(target) if matches!(**target, Expr::Ident(_)));
+                        if plain {
+                            self.visit_expr(right);
+                        }
+                    }
+                    Expr::Assign(AssignExpr {
+                        left: AssignLeft::Pat(_),
+                        ..
+                    }) => self.found = true,
+                    Expr::Assign(AssignExpr { right, .. })
+                        if self.options.split_assignments && matches!(**right, Expr::Assign(_)) =>
+                    {
+                        self.found = true
+                    }
+                    expr => walk_expr(self, expr),
+                }
+            }
+            fn visit_func(&mut self, _func: &Func<'a>) {}
+        }
+        let mut hoists = Hoists {
+            purity: &self.purity,
+            options: self.options,
+            found: false,
+        };
+        hoists.visit_expr(expr);
+        hoists.found
+    }
+    // Whether the operand of a short-circuit or conditional needs an `if` of
+    // its own: it hoisted lines, or it does something that only some paths
+    // should.
+    fn must_lower(&self, block: &Block) -> bool {
+        !block.action_lines.is_empty()
+            || block
+                .value
+                .as_ref()
+                .is_some_and(|value| self.purity.has_side_effects(value))
+    }
+    // A line that evaluates `value` and throws it away, or none when that
+    // can't make a difference.
+    fn discard<'a>(&self, value: Expr<'a>) -> Option<Line<'a>> {
+        if self.purity.is_dead(&value) {
+            None
+        } else {
+            Some(Line::Part(ProgramPart::Stmt(Stmt::Expr(value))))
+        }
+    }
+    // Runs `body` for its effects only when `test` is truthy (or falsy, if
+    // `negate` is set). Chains like `a && b && c()` become nested ifs.
+    fn guard<'a>(&mut self, test: Expr<'a>, negate: bool, body: Expr<'a>) -> Vec<Line<'a>>
+    where
+        'b: 'a,
+    {
+        let mut tests = vec![];
+        chain_operands(test, negate, &mut tests);
+
+        let t_blocks: Vec<Block<'a>> = tests
+            .into_iter()
+            .map(|test| self.blockify(Blockable::Expr(test)))
+            .collect();
+
+        t_blocks
+            .into_iter()
+            .rev()
+            .fold(self.effects(body), |inner, mut t_block| {
+                let test = t_block.expr();
+                let mut lines = t_block.lines();
+                if inner.is_empty() {
+                    lines.extend(self.discard(test));
+                } else {
+                    lines.push(Line::Part(ProgramPart::Stmt(Stmt::If(IfStmt {
+                        test: if negate { self::negate(test) } else { test },
+                        consequent: wrap(Block::from_lines(inner)),
+                        alternate: None,
+                    }))));
+                }
+                lines
+            })
+    }
+}
+
+// Splits `a && b && c` (or `a || b || c`, when `negate` is set) into the
+// operands that guard each other.
+fn chain_operands<'a>(expr: Expr<'a>, negate: bool, operands: &mut Vec<Expr<'a>>) {
+    match expr {
+        Expr::Logical(LogicalExpr {
+            left,
+            right,
+            operator,
+        }) if (operator == LogicalOp::Or) == negate => {
+            chain_operands(*left, negate, operands);
+            chain_operands(*right, negate, operands);
+        }
+        expr => operands.push(expr),
+    }
+}
+
+// What `deblockify` gives back.
+#[derive(Debug)]
+pub struct Deblockified {
+    pub code: String,
+    pub rounds: usize,
+    // False when the last round still changed something.
+    pub fixed_point: bool,
+}
+
+// Why `deblockify` failed.
+#[derive(Debug)]
+pub enum Error {
+    // The script passed in doesn't parse.
+    Parse(ressa::Error),
+    // A round printed code that doesn't parse, which is a bug in lowering or
+    // printing rather than in the script. `round` counts from 1, and `code`
+    // is what it printed.
+    Reparse {
+        round: usize,
+        code: String,
+        error: ressa::Error,
+    },
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Parse(error) => write!(f, "Failed to parse: {}", error),
+            Error::Reparse { round, error, .. } => write!(
+                f,
+                "Round {} printed code that doesn't parse: {}",
+                round, error
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Parse(error) | Error::Reparse { error, .. } => Some(error),
+        }
+    }
+}
+
+const MAX_ROUNDS: usize = 10;
+
+// Deblockifies a script. Lowering can expose code that another round would
+// lower further, so this runs rounds until one changes nothing, which makes
+// running it on its own output a no-op. `make_namer` gives each round a
+// fresh namer, so names come out the same in every round.
+//
+// Fails with `Error::Parse` if `code` doesn't parse, and with
+// `Error::Reparse` if a round's output doesn't, which is a bug here.
+pub fn deblockify<'n, F>(
+    code: &str,
+    options: Options,
+    mut make_namer: F,
+) -> Result<Deblockified, Error>
+where
+    F: FnMut() -> Box<dyn Namer + 'n>,
+{
+    let mut code = code.to_string();
+    // The temps of every round, so later rounds can clean up after earlier
+    // ones.
+    let mut temps = HashSet::new();
+    let mut rounds = 0;
+    loop {
+        let next = round(&code, options, make_namer(), &mut temps).map_err(|error| {
+            if rounds == 0 {
+                Error::Parse(error)
+            } else {
+                Error::Reparse {
+                    round: rounds,
+                    code: code.clone(),
+                    error,
+                }
+            }
+        })?;
+        rounds += 1;
+        let fixed_point = next == code;
+        code = next;
+        if fixed_point || rounds == MAX_ROUNDS {
+            return Ok(Deblockified {
+                code,
+                rounds,
+                fixed_point,
+            });
+        }
+    }
+}
+
+// One round: blockifies `code`, cleans up the temps and prints it back out.
+fn round(
+    code: &str,
+    options: Options,
+    namer: Box<dyn Namer + '_>,
+    temps: &mut HashSet<String>,
+) -> Result<String, ressa::Error> {
+    let mut parser = Parser::new(code)?;
+    let program = parser.parse()?;
+
+    let mut names = Names::default();
+    walk_program(&mut names, &program);
+
+    let purity = Purity::new(&program, options.assumptions);
+
+    let mut blockifier = Blockifier::new(namer, names.names, purity, options);
+    let block = blockifier.scoped(Blockable::Program(program));
+    temps.extend(blockifier.temps);
+    let mut program = block.program();
+    inline_temps(&mut program, temps, &blockifier.purity);
+
+    let mut out = vec![];
+    Writer::new(&mut out).write_program(&program).expect("Ono");
+    Ok(String::from_utf8(out).expect("Printed invalid UTF-8"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::naming::Sequential;
+
+    // Deblockifies `code` with `_t1`, `_t2`, ... names and gives back its
+    // lines, trimmed and joined with spaces.
+    fn lower(code: &str) -> String {
+        let make_namer = || -> Box<dyn Namer> { Box::new(Sequential::default()) };
+        let output = deblockify(code, Options::default(), make_namer)
+            .unwrap_or_else(|error| panic!("{}", error));
+        output
+            .code
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    #[test]
+    fn spills_a_variable_that_a_later_line_assigns() {
+        assert_eq!(
+            lower("var a; f(a, (a = 1, 2));"),
+            "var a; const _t1 = a; a = 1; f(_t1, 2);"
+        );
+    }
+
+    #[test]
+    fn keeps_values_nothing_can_change() {
+        assert_eq!(
+            lower("f(1, this, function () {}, (g(), 2));"),
+            "const _t1 = f; g(); _t1(1, this, function() { }, 2);"
+        );
+    }
+
+    #[test]
+    fn spills_a_global_when_a_later_line_runs_code() {
+        assert_eq!(
+            lower("var x; f(x, (g(), 2));"),
+            "var x; const _t1 = f; const _t2 = x; g(); _t1(_t2, 2);"
+        );
+    }
+
+    #[test]
+    fn keeps_an_uncaptured_local_when_a_later_line_runs_code() {
+        assert_eq!(
+            lower("function h() { var x = 1; x.y(x, (g(), 2)); }"),
+            "function h() { var x = 1; g(); x.y(x, 2); }"
+        );
+    }
+
+    #[test]
+    fn spills_a_local_that_a_later_line_assigns() {
+        assert_eq!(
+            lower("function h() { var x = 1; x.y(x, (x = 2, 3)); }"),
+            "function h() { var x = 1; const _t1 = x; const _t2 = x; x = 2; _t1.y(_t2, 3); }"
+        );
+    }
+
+    #[test]
+    fn spills_a_captured_local_when_a_later_line_runs_code() {
+        assert_eq!(
+            lower("function h() { var x = 1; k = () => x; x.y(x, (g(), 2)); }"),
+            "function h() { var x = 1; k = () => x; const _t1 = x; const _t2 = x; g(); _t1.y(_t2, 2); }"
+        );
+    }
+
+    #[test]
+    fn spills_a_local_across_an_await() {
+        assert_eq!(
+            lower("async function h() { var x = 1; x.y(x, (await g(), 1)); }"),
+            "async function h() { var x = 1; const _t1 = x; const _t2 = x; await g(); _t1.y(_t2, 1); }"
+        );
+    }
+
+    #[test]
+    fn never_spills_super() {
+        assert_eq!(
+            lower("class A extends B { m() { super.m(x, (g(), 1)); } }"),
+            "class A extends B { m(){ const _t1 = x; g(); super.m(_t1, 1); } }"
+        );
+    }
+}
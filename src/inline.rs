@@ -0,0 +1,929 @@
+use crate::purity::Purity;
+use crate::visit::{self, Bindings, Names, Suspends, Visit, WritesTo};
+use crate::visit_mut::{self, VisitMut};
+use resast::expr::Expr;
+use resast::prelude::*;
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+
+// Cleans up after the Blockifier: a temp that's read once, right at the start
+// of the statement after it, is put back in place, and one whose value is
+// never read is dropped, leaving whatever side effects `purity` finds. Only names in `temps` are
+// touched, and since temps are unique across the program, their uses can be
+// counted once for the whole tree.
+pub fn inline_temps(program: &mut Program, temps: &HashSet<String>, purity: &Purity) {
+    let mut inliner = Inliner {
+        temps,
+        uses: count(temps, program_parts(program)),
+        purity,
+    };
+    visit_mut::walk_program(&mut inliner, program);
+    // Merging two temps can leave a `const` that's assigned again.
+    let mut demote = Demote {
+        uses: count(temps, program_parts(program)),
+    };
+    visit_mut::walk_program(&mut demote, program);
+}
+
+fn program_parts<'p, 'a>(program: &'p Program<'a>) -> &'p [ProgramPart<'a>] {
+    match program {
+        Program::Script(parts) | Program::Mod(parts) => parts,
+    }
+}
+
+fn count(temps: &HashSet<String>, parts: &[ProgramPart]) -> HashMap<String, Uses> {
+    let mut counter = Counter {
+        temps,
+        uses: HashMap::new(),
+    };
+    for part in parts {
+        counter.visit_part(part);
+    }
+    counter.uses
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+struct Uses {
+    reads: usize,
+    // Declarations count as writes, with or without an initializer.
+    writes: usize,
+}
+
+struct Counter<'t> {
+    temps: &'t HashSet<String>,
+    uses: HashMap<String, Uses>,
+}
+
+impl<'t> Counter<'t> {
+    fn uses(&mut self, name: &str) -> Option<&mut Uses> {
+        if self.temps.contains(name) {
+            Some(self.uses.entry(name.to_string()).or_default())
+        } else {
+            None
+        }
+    }
+}
+
+impl<'a, 't> Visit<'a> for Counter<'t> {
+    fn visit_expr(&mut self, expr: &Expr<'a>) {
+        match expr {
+            Expr::Ident(Ident { name }) => {
+                if let Some(uses) = self.uses(name) {
+                    uses.reads += 1;
+                }
+            }
+            Expr::Assign(AssignExpr {
+                operator,
+                left: AssignLeft::Expr(left),
+                right,
+            }) => {
+                if let Expr::Ident(Ident { name }) = &**left {
+                    if let Some(uses) = self.uses(name) {
+                        uses.writes += 1;
+                        if *operator != AssignOp::Equal {
+                            uses.reads += 1;
+                        }
+                    }
+                } else {
+                    self.visit_expr(left);
+                }
+                self.visit_expr(right);
+                return;
+            }
+            Expr::Update(UpdateExpr { argument, .. }) => {
+                if let Expr::Ident(Ident { name }) = &**argument {
+                    if let Some(uses) = self.uses(name) {
+                        uses.reads += 1;
+                        uses.writes += 1;
+                    }
+                    return;
+                }
+            }
+            // `{t}` reads `t`, though it's written like a pattern.
+            Expr::Obj(props) => {
+                for prop in props {
+                    match prop {
+                        ObjProp::Prop(Prop {
+                            key: PropKey::Pat(Pat::Ident(Ident { name })),
+                            value: PropValue::None,
+                            ..
+                        }) => {
+                            if let Some(uses) = self.uses(name) {
+                                uses.reads += 1;
+                            }
+                        }
+                        ObjProp::Prop(prop) => visit::walk_prop(self, prop),
+                        ObjProp::Spread(expr) => self.visit_expr(expr),
+                    }
+                }
+                return;
+            }
+            _ => {}
+        }
+        visit::walk_expr(self, expr)
+    }
+    fn visit_pat(&mut self, pat: &Pat<'a>) {
+        if let Pat::Ident(Ident { name }) = pat {
+            if let Some(uses) = self.uses(name) {
+                uses.writes += 1;
+            }
+        }
+        visit::walk_pat(self, pat)
+    }
+}
+
+struct Inliner<'p> {
+    temps: &'p HashSet<String>,
+    uses: HashMap<String, Uses>,
+    purity: &'p Purity,
+}
+
+// How a temp gets its value: `const t = value` or `t = value`.
+enum Def<'a> {
+    Declared(String, Expr<'a>),
+    Assigned(String, Expr<'a>),
+}
+
+impl<'a> Def<'a> {
+    fn of(part: &ProgramPart<'a>) -> Option<Def<'a>> {
+        match part {
+            ProgramPart::Decl(Decl::Var(VarKind::Let, decls))
+            | ProgramPart::Decl(Decl::Var(VarKind::Const, decls)) => match &decls[..] {
+                [VarDecl {
+                    id: Pat::Ident(Ident { name }),
+                    init: Some(value),
+                }] => Some(Def::Declared(name.to_string(), value.clone())),
+                _ => None,
+            },
+            ProgramPart::Stmt(Stmt::Expr(Expr::Assign(AssignExpr {
+                operator: AssignOp::Equal,
+                left: AssignLeft::Expr(left),
+                right,
+            }))) => match &**left {
+                Expr::Ident(Ident { name }) => {
+                    Some(Def::Assigned(name.to_string(), (**right).clone()))
+                }
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+}
+
+impl<'p> Inliner<'p> {
+    // Takes back the uses in a value that was dropped.
+    fn forget(&mut self, value: &Expr) {
+        let mut counter = Counter {
+            temps: self.temps,
+            uses: HashMap::new(),
+        };
+        counter.visit_expr(value);
+        for (name, dropped) in counter.uses {
+            let uses = self.uses_mut(&name);
+            uses.reads -= dropped.reads;
+            uses.writes -= dropped.writes;
+        }
+    }
+    fn uses(&self, name: &str) -> Uses {
+        self.uses.get(name).copied().unwrap_or_default()
+    }
+    fn uses_mut(&mut self, name: &str) -> &mut Uses {
+        self.uses.entry(name.to_string()).or_default()
+    }
+    // Tries each rewrite on the definition at `idx`. Returns whether `parts`
+    // changed, in which case `idx` holds something new to look at.
+    fn rewrite<'a>(&mut self, parts: &mut Vec<ProgramPart<'a>>, idx: usize) -> bool {
+        let (name, value, own_writes) = match Def::of(&parts[idx]) {
+            Some(Def::Declared(name, value)) => (name, value, 1),
+            // The other write is the `var` that declares it.
+            Some(Def::Assigned(name, value)) => (name, value, 2),
+            None => return false,
+        };
+        if !self.uses.contains_key(&name) {
+            return false;
+        }
+        let uses = self.uses(&name);
+
+        if uses.reads == 0 {
+            match value {
+                value if self.purity.is_dead(&value) => {
+                    parts.remove(idx);
+                    self.forget(&value);
+                }
+                // These can't be written as statements.
+                Expr::Obj(_) | Expr::Class(_) => return false,
+                value => parts[idx] = ProgramPart::Stmt(Stmt::Expr(value)),
+            }
+            self.uses_mut(&name).writes -= 1;
+            return true;
+        }
+
+        if let Expr::Ident(Ident { name: source }) = &value {
+            if self.coalesce(parts, idx, &name, source, own_writes) {
+                return true;
+            }
+        }
+
+        if uses.reads != 1 || uses.writes != own_writes || idx + 1 == parts.len() {
+            return false;
+        }
+        let lead = Lead {
+            name: &name,
+            value: &value,
+            constant: is_constant(&value),
+            runs_code: self.purity.runs_code(&value),
+            temps: self.temps,
+        };
+        if lead.part(&parts[idx + 1]) == Found::Yes {
+            let mut replace = Replace {
+                name: &name,
+                value: Some(value),
+            };
+            replace.visit_part(&mut parts[idx + 1]);
+            parts.remove(idx);
+            *self.uses_mut(&name) = Uses {
+                reads: 0,
+                writes: own_writes - 1,
+            };
+            return true;
+        }
+
+        // let t = a; if (!t) { ...; t = b; }  =>  if (!a) { ...; b; }
+        // when the `if` is the only place that reads `t`.
+        if own_writes == 1 && self.guard(&name, value, &mut parts[idx + 1], uses.writes - 1) {
+            parts.remove(idx);
+            *self.uses_mut(&name) = Uses::default();
+            return true;
+        }
+        false
+    }
+    // let t2 = t1; ...  =>  ...
+    // with `t2` renamed to `t1`. That's fine when `t2` is never assigned
+    // again, so it's just another name for `t1`, or when this is the last use
+    // of a `t1` declared earlier in the same list, so nothing else sees it
+    // change. Either way `t1` mustn't be assigned while `t2` is around.
+    fn coalesce(
+        &mut self,
+        parts: &mut Vec<ProgramPart>,
+        idx: usize,
+        name: &str,
+        source: &str,
+        own_writes: usize,
+    ) -> bool {
+        if name == source || !self.uses.contains_key(source) {
+            return false;
+        }
+        let temps: HashSet<String> = [name, source].iter().map(|name| name.to_string()).collect();
+        let rest = count(&temps, &parts[idx + 1..]);
+        let uses = self.uses(name);
+        let source_uses = self.uses(source);
+        let later = rest.get(name).copied().unwrap_or_default();
+        if rest.get(source).is_some_and(|uses| uses.writes > 0)
+            || later.reads != uses.reads
+            || later.writes + own_writes != uses.writes
+        {
+            return false;
+        }
+        if later.writes > 0 {
+            let before = count(&temps, &parts[..idx]);
+            let declared = parts[..idx].iter().any(|part| match part {
+                ProgramPart::Decl(Decl::Var(VarKind::Let, decls))
+                | ProgramPart::Decl(Decl::Var(VarKind::Const, decls)) => decls
+                    .iter()
+                    .any(|decl| matches!(&decl.id, Pat::Ident(Ident { name }) if name == source)),
+                _ => false,
+            });
+            let before = before.get(source).copied().unwrap_or_default();
+            if !declared
+                || before.reads + 1 != source_uses.reads
+                || before.writes != source_uses.writes
+            {
+                return false;
+            }
+        }
+
+        let mut rename = Rename {
+            from: name,
+            to: source,
+        };
+        for part in &mut parts[idx + 1..] {
+            rename.visit_part(part);
+        }
+        parts.remove(idx);
+        *self.uses_mut(source) = Uses {
+            reads: source_uses.reads - 1 + later.reads,
+            writes: source_uses.writes + later.writes,
+        };
+        *self.uses_mut(name) = Uses {
+            reads: 0,
+            writes: own_writes - 1,
+        };
+        true
+    }
+    // let t; if (a) { t = b; } else { t = c; } x = t;
+    //   =>  if (a) { x = b; } else { x = c; }
+    // when every branch ends by assigning `t`, and `t` is read only after.
+    // Works for `return t;` too.
+    fn sink<'a>(&mut self, parts: &mut Vec<ProgramPart<'a>>, idx: usize) -> bool {
+        if idx + 1 >= parts.len() || !matches!(parts[idx], ProgramPart::Stmt(Stmt::If(_))) {
+            return false;
+        }
+        // The kind is set when `x` is declared there, as in `var x = t;`.
+        let (name, target, kind) = match &parts[idx + 1] {
+            ProgramPart::Stmt(Stmt::Return(Some(Expr::Ident(Ident { name })))) => {
+                (name.to_string(), None, None)
+            }
+            ProgramPart::Stmt(Stmt::Expr(Expr::Assign(AssignExpr {
+                operator: AssignOp::Equal,
+                left: AssignLeft::Expr(left),
+                right,
+            }))) => match (&**left, &**right) {
+                (Expr::Ident(target), Expr::Ident(Ident { name })) if target.name != *name => {
+                    (name.to_string(), Some(target.clone()), None)
+                }
+                _ => return false,
+            },
+            ProgramPart::Decl(Decl::Var(kind @ VarKind::Var, decls))
+            | ProgramPart::Decl(Decl::Var(kind @ VarKind::Let, decls)) => match &decls[..] {
+                [VarDecl {
+                    id: Pat::Ident(target),
+                    init: Some(Expr::Ident(Ident { name })),
+                }] if target.name != *name => (name.to_string(), Some(target.clone()), Some(*kind)),
+                _ => return false,
+            },
+            _ => return false,
+        };
+        let uses = match self.uses.get(&name) {
+            Some(uses) if uses.reads == 1 => *uses,
+            _ => return false,
+        };
+        let branches = match &mut parts[idx] {
+            ProgramPart::Stmt(stmt) => stmt,
+            _ => return false,
+        };
+        let tails = match tails(branches, &name) {
+            Some(tails) if tails + 1 == uses.writes => tails,
+            _ => return false,
+        };
+        // Moving a `let` up mustn't put anything in its dead zone.
+        if let (Some(VarKind::Let), Some(target)) = (kind, &target) {
+            let mut names = Names::default();
+            visit::walk_stmt(&mut names, branches);
+            if names.names.contains(&target.name[..]) {
+                return false;
+            }
+        }
+
+        sink_tails(branches, &name, &|value| match &target {
+            Some(target) => Stmt::Expr(Expr::Assign(AssignExpr {
+                operator: AssignOp::Equal,
+                left: AssignLeft::Expr(Box::new(Expr::Ident(target.clone()))),
+                right: Box::new(value),
+            })),
+            None => Stmt::Return(Some(value)),
+        });
+        parts.remove(idx + 1);
+        *self.uses_mut(&name) = Uses {
+            reads: 0,
+            writes: 1,
+        };
+        if let Some(target) = target {
+            if let Some(uses) = self.uses.get_mut(&target.name[..]) {
+                uses.writes += tails - 1;
+            }
+            if let Some(kind) = kind {
+                let decl = VarDecl {
+                    id: Pat::Ident(target.clone()),
+                    init: None,
+                };
+                parts.insert(idx, ProgramPart::Decl(Decl::Var(kind, vec![decl])));
+                if let Some(uses) = self.uses.get_mut(&target.name[..]) {
+                    uses.writes += 1;
+                }
+            }
+        }
+        true
+    }
+    fn guard<'a>(
+        &mut self,
+        name: &str,
+        value: Expr<'a>,
+        part: &mut ProgramPart<'a>,
+        writes: usize,
+    ) -> bool {
+        let IfStmt {
+            test,
+            consequent,
+            alternate,
+        } = match part {
+            ProgramPart::Stmt(Stmt::If(if_stmt)) => if_stmt,
+            _ => return false,
+        };
+        let test_reads = match test {
+            Expr::Ident(Ident { name: read }) => read == name,
+            Expr::Unary(UnaryExpr {
+                operator: UnaryOp::Not,
+                argument,
+                ..
+            }) => matches!(&**argument, Expr::Ident(Ident { name: read }) if read == name),
+            _ => false,
+        };
+        let branches: Vec<&mut Stmt<'a>> = iter_branches(consequent, alternate);
+        let stores: usize = branches
+            .iter()
+            .map(|branch| match branch {
+                Stmt::Block(BlockStmt(parts)) => {
+                    parts.iter().filter(|part| stores_to(part, name)).count()
+                }
+                _ => 0,
+            })
+            .sum();
+        if !test_reads || stores != writes {
+            return false;
+        }
+
+        for branch in branches {
+            if let Stmt::Block(BlockStmt(parts)) = branch {
+                let mut idx = 0;
+                while idx < parts.len() {
+                    if stores_to(&parts[idx], name) {
+                        match Def::of(&parts[idx]) {
+                            Some(Def::Assigned(_, value)) if self.purity.is_dead(&value) => {
+                                parts.remove(idx);
+                                self.forget(&value);
+                                continue;
+                            }
+                            Some(Def::Assigned(_, value)) => {
+                                parts[idx] = ProgramPart::Stmt(Stmt::Expr(value))
+                            }
+                            _ => {}
+                        }
+                    }
+                    idx += 1;
+                }
+            }
+        }
+        let mut replace = Replace {
+            name,
+            value: Some(value),
+        };
+        replace.visit_expr(test);
+        true
+    }
+}
+
+fn iter_branches<'s, 'a>(
+    consequent: &'s mut Box<Stmt<'a>>,
+    alternate: &'s mut Option<Box<Stmt<'a>>>,
+) -> Vec<&'s mut Stmt<'a>> {
+    let mut branches = vec![&mut **consequent];
+    if let Some(alternate) = alternate {
+        branches.push(&mut **alternate);
+    }
+    branches
+}
+
+// `name = ...;`
+fn stores_to(part: &ProgramPart, name: &str) -> bool {
+    match Def::of(part) {
+        Some(Def::Assigned(stored, _)) => stored == name,
+        _ => false,
+    }
+}
+
+fn stored<'s, 'a>(stmt: &'s Stmt<'a>, name: &str) -> Option<&'s Expr<'a>> {
+    match stmt {
+        Stmt::Expr(Expr::Assign(AssignExpr {
+            operator: AssignOp::Equal,
+            left: AssignLeft::Expr(left),
+            right,
+        })) => match &**left {
+            Expr::Ident(Ident { name: stored }) if stored == name => Some(right),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+// How many assignments to `name` end the branches of an `if`, or `None`
+// unless every way through ends with one.
+fn tails(stmt: &Stmt, name: &str) -> Option<usize> {
+    match stmt {
+        Stmt::If(IfStmt {
+            consequent,
+            alternate: Some(alternate),
+            ..
+        }) => Some(tails(consequent, name)? + tails(alternate, name)?),
+        Stmt::Block(BlockStmt(parts)) => match parts.last() {
+            Some(ProgramPart::Stmt(last)) => tails(last, name),
+            _ => None,
+        },
+        stmt => stored(stmt, name).map(|_| 1),
+    }
+}
+
+fn sink_tails<'a>(stmt: &mut Stmt<'a>, name: &str, store: &dyn Fn(Expr<'a>) -> Stmt<'a>) {
+    match stmt {
+        Stmt::If(IfStmt {
+            consequent,
+            alternate: Some(alternate),
+            ..
+        }) => {
+            sink_tails(consequent, name, store);
+            sink_tails(alternate, name, store);
+        }
+        Stmt::Block(BlockStmt(parts)) => {
+            if let Some(ProgramPart::Stmt(last)) = parts.last_mut() {
+                sink_tails(last, name, store);
+            }
+        }
+        stmt => {
+            if let Some(value) = stored(stmt, name) {
+                *stmt = store(value.clone());
+            }
+        }
+    }
+}
+
+// Values that can be dropped, or moved past anything, without it showing.
+fn is_constant(expr: &Expr) -> bool {
+    match expr {
+        Expr::Lit(Lit::Template(TemplateLit { expressions, .. })) => expressions.is_empty(),
+        Expr::Lit(_) | Expr::This | Expr::Func(_) | Expr::ArrowFunc(_) => true,
+        _ => false,
+    }
+}
+
+impl<'a, 'p> VisitMut<'a> for Inliner<'p> {
+    fn visit_parts(&mut self, parts: &mut Vec<ProgramPart<'a>>) {
+        visit_mut::walk_parts(self, parts);
+        let mut idx = 0;
+        while idx < parts.len() {
+            if !self.rewrite(parts, idx) && !self.sink(parts, idx) {
+                idx += 1;
+            }
+        }
+        // Declarations of temps that are no longer assigned or read.
+        for part in parts.iter_mut() {
+            if let ProgramPart::Decl(Decl::Var(_, decls)) = part {
+                let uses = &self.uses;
+                decls.retain(|decl| match decl {
+                    VarDecl {
+                        id: Pat::Ident(Ident { name }),
+                        init: None,
+                    } => match uses.get(&name[..]) {
+                        Some(uses) => uses.reads > 0 || uses.writes > 1,
+                        None => true,
+                    },
+                    _ => true,
+                });
+            }
+        }
+        parts.retain(
+            |part| !matches!(part, ProgramPart::Decl(Decl::Var(_, decls)) if decls.is_empty()),
+        );
+    }
+}
+
+// Renames a temp. Temps are never written shorthand, so `{t}` can't come up.
+struct Rename<'n> {
+    from: &'n str,
+    to: &'n str,
+}
+
+impl<'a, 'n> VisitMut<'a> for Rename<'n> {
+    fn visit_expr(&mut self, expr: &mut Expr<'a>) {
+        match expr {
+            Expr::Ident(Ident { name }) if name == self.from => {
+                *name = Cow::Owned(self.to.to_string())
+            }
+            _ => visit_mut::walk_expr(self, expr),
+        }
+    }
+    fn visit_pat(&mut self, pat: &mut Pat<'a>) {
+        match pat {
+            Pat::Ident(Ident { name }) if name == self.from => {
+                *name = Cow::Owned(self.to.to_string())
+            }
+            _ => visit_mut::walk_pat(self, pat),
+        }
+    }
+}
+
+// Turns `const` temps that are assigned more than once into `let`.
+struct Demote {
+    uses: HashMap<String, Uses>,
+}
+
+impl Demote {
+    fn reassigned(&self, decls: &[VarDecl]) -> bool {
+        decls.iter().any(|decl| {
+            let mut bindings = Bindings::default();
+            bindings.visit_pat(&decl.id);
+            bindings
+                .names
+                .iter()
+                .any(|name| self.uses.get(&name[..]).is_some_and(|uses| uses.writes > 1))
+        })
+    }
+}
+
+impl<'a> VisitMut<'a> for Demote {
+    fn visit_part(&mut self, part: &mut ProgramPart<'a>) {
+        if let ProgramPart::Decl(Decl::Var(kind @ VarKind::Const, decls)) = part {
+            if self.reassigned(decls) {
+                *kind = VarKind::Let;
+            }
+        }
+        visit_mut::walk_part(self, part)
+    }
+    fn visit_stmt(&mut self, stmt: &mut Stmt<'a>) {
+        match stmt {
+            Stmt::For(ForStmt {
+                init: Some(LoopInit::Variable(kind @ VarKind::Const, decls)),
+                ..
+            }) if self.reassigned(decls) => *kind = VarKind::Let,
+            Stmt::ForIn(ForInStmt {
+                left: LoopLeft::Variable(kind @ VarKind::Const, decl),
+                ..
+            })
+            | Stmt::ForOf(ForOfStmt {
+                left: LoopLeft::Variable(kind @ VarKind::Const, decl),
+                ..
+            }) if self.reassigned(std::slice::from_ref(decl)) => *kind = VarKind::Let,
+            _ => {}
+        }
+        visit_mut::walk_stmt(self, stmt)
+    }
+}
+
+// Swaps the first read of `name` for `value`.
+struct Replace<'n, 'a> {
+    name: &'n str,
+    value: Option<Expr<'a>>,
+}
+
+impl<'n, 'a> VisitMut<'a> for Replace<'n, 'a> {
+    fn visit_expr(&mut self, expr: &mut Expr<'a>) {
+        match expr {
+            Expr::Ident(Ident { name }) if name == self.name => {
+                if let Some(value) = self.value.take() {
+                    *expr = value;
+                }
+            }
+            _ => visit_mut::walk_expr(self, expr),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Found {
+    Yes,
+    // Nothing run so far could tell the difference.
+    NotYet,
+    // Something observable ran first.
+    Blocked,
+}
+
+// Whether `name` is the first thing a statement evaluates, ignoring anything
+// `value` couldn't affect or be affected by. A variable counts as unaffected
+// when `value` doesn't assign to it and can't run code that might, or when
+// it's a temp, which only the lines made for it touch.
+struct Lead<'n, 'e, 'a> {
+    name: &'n str,
+    value: &'e Expr<'a>,
+    // `value` is a literal or function, which can move past anything.
+    constant: bool,
+    // `value` could call something that assigns to a variable from a closure.
+    runs_code: bool,
+    temps: &'n HashSet<String>,
+}
+
+impl<'n, 'e, 'a> Lead<'n, 'e, 'a> {
+    // Code that could run user code, like a getter or `valueOf`.
+    fn effect(&self) -> Found {
+        if self.constant {
+            Found::NotYet
+        } else {
+            Found::Blocked
+        }
+    }
+    fn read(&self, name: &str) -> Found {
+        if name == self.name {
+            return Found::Yes;
+        }
+        let mut writes = WritesTo::new(name);
+        writes.visit_expr(self.value);
+        let mut suspends = Suspends::default();
+        suspends.visit_expr(self.value);
+        let reachable = self.runs_code && !self.temps.contains(name);
+        if self.constant || (!writes.found && !suspends.found && !reachable) {
+            Found::NotYet
+        } else {
+            Found::Blocked
+        }
+    }
+    fn all<'x, I>(&self, exprs: I) -> Found
+    where
+        I: IntoIterator<Item = &'x Expr<'a>>,
+        'a: 'x,
+    {
+        for expr in exprs {
+            match self.expr(expr) {
+                Found::NotYet => {}
+                found => return found,
+            }
+        }
+        Found::NotYet
+    }
+    // Runs `first`, and `then` if nothing was found yet.
+    fn then(&self, first: Found, then: impl FnOnce() -> Found) -> Found {
+        match first {
+            Found::NotYet => then(),
+            found => found,
+        }
+    }
+    fn part(&self, part: &ProgramPart<'a>) -> Found {
+        match part {
+            ProgramPart::Decl(Decl::Var(_, decls)) => {
+                for VarDecl { id, init } in decls {
+                    if let Some(init) = init {
+                        match self.expr(init) {
+                            Found::NotYet => {}
+                            found => return found,
+                        }
+                    }
+                    if !matches!(id, Pat::Ident(_)) {
+                        return Found::Blocked;
+                    }
+                }
+                Found::NotYet
+            }
+            ProgramPart::Stmt(stmt) => match stmt {
+                Stmt::Expr(expr) | Stmt::Throw(expr) | Stmt::Return(Some(expr)) => self.expr(expr),
+                Stmt::If(IfStmt { test, .. }) => self.expr(test),
+                Stmt::Switch(SwitchStmt { discriminant, .. }) => self.expr(discriminant),
+                Stmt::ForIn(ForInStmt { right, .. }) | Stmt::ForOf(ForOfStmt { right, .. }) => {
+                    self.expr(right)
+                }
+                _ => Found::Blocked,
+            },
+            _ => Found::Blocked,
+        }
+    }
+    fn expr(&self, expr: &Expr<'a>) -> Found {
+        match expr {
+            Expr::Ident(Ident { name }) => self.read(name),
+            Expr::Lit(Lit::Template(TemplateLit { expressions, .. })) => {
+                for expr in expressions {
+                    match self.then(self.expr(expr), || self.effect()) {
+                        Found::NotYet => {}
+                        found => return found,
+                    }
+                }
+                Found::NotYet
+            }
+            Expr::Lit(_) | Expr::This | Expr::Func(_) | Expr::ArrowFunc(_) => Found::NotYet,
+            Expr::Array(elems) => {
+                for elem in elems.iter().flatten() {
+                    match self.expr(elem) {
+                        Found::NotYet => {}
+                        found => return found,
+                    }
+                }
+                Found::NotYet
+            }
+            Expr::Spread(argument) => self.then(self.expr(argument), || self.effect()),
+            Expr::Obj(props) => {
+                for prop in props {
+                    let found = match prop {
+                        ObjProp::Prop(Prop {
+                            key: PropKey::Pat(Pat::Ident(Ident { name })),
+                            value: PropValue::None,
+                            ..
+                        }) if name == self.name => Found::Blocked,
+                        ObjProp::Prop(Prop {
+                            key: PropKey::Pat(Pat::Ident(Ident { name })),
+                            value: PropValue::None,
+                            ..
+                        }) => self.read(name),
+                        ObjProp::Prop(Prop {
+                            key,
+                            value,
+                            computed,
+                            ..
+                        }) => {
+                            let key = match key {
+                                PropKey::Expr(key) if *computed => self.expr(key),
+                                PropKey::Pat(_) if *computed => Found::Blocked,
+                                _ => Found::NotYet,
+                            };
+                            self.then(key, || match value {
+                                PropValue::Expr(value) => self.expr(value),
+                                PropValue::Pat(_) => Found::Blocked,
+                                PropValue::None => Found::NotYet,
+                            })
+                        }
+                        ObjProp::Spread(expr) => self.then(self.expr(expr), || self.effect()),
+                    };
+                    if found != Found::NotYet {
+                        return found;
+                    }
+                }
+                Found::NotYet
+            }
+            Expr::Unary(UnaryExpr {
+                operator, argument, ..
+            }) => self.then(self.expr(argument), || match operator {
+                UnaryOp::Not | UnaryOp::TypeOf | UnaryOp::Void => Found::NotYet,
+                _ => self.effect(),
+            }),
+            Expr::Binary(BinaryExpr { left, right, .. }) => {
+                self.then(self.all(vec![&**left, &**right]), || self.effect())
+            }
+            // Only the left side is sure to run.
+            Expr::Logical(LogicalExpr { left, .. }) => match self.expr(left) {
+                Found::Yes => Found::Yes,
+                _ => Found::Blocked,
+            },
+            Expr::Conditional(ConditionalExpr { test, .. }) => match self.expr(test) {
+                Found::Yes => Found::Yes,
+                _ => Found::Blocked,
+            },
+            Expr::Assign(AssignExpr {
+                operator,
+                left,
+                right,
+            }) => {
+                let target = match left {
+                    AssignLeft::Expr(left) => match &**left {
+                        Expr::Ident(Ident { name }) if name == self.name => Found::Blocked,
+                        Expr::Ident(Ident { name }) if *operator != AssignOp::Equal => {
+                            self.read(name)
+                        }
+                        Expr::Ident(_) => Found::NotYet,
+                        Expr::Member(MemberExpr {
+                            object,
+                            property,
+                            computed,
+                        }) => {
+                            let parts = self.then(self.expr(object), || {
+                                if *computed {
+                                    self.expr(property)
+                                } else {
+                                    Found::NotYet
+                                }
+                            });
+                            if *operator == AssignOp::Equal {
+                                parts
+                            } else {
+                                self.then(parts, || self.effect())
+                            }
+                        }
+                        _ => Found::Blocked,
+                    },
+                    // A pattern is only assigned after the right side runs.
+                    AssignLeft::Pat(_) => {
+                        return match self.expr(right) {
+                            Found::Yes => Found::Yes,
+                            _ => Found::Blocked,
+                        }
+                    }
+                };
+                self.then(self.then(target, || self.expr(right)), || self.effect())
+            }
+            Expr::Member(MemberExpr {
+                object,
+                property,
+                computed,
+            }) => {
+                let parts = self.then(self.expr(object), || {
+                    if *computed {
+                        self.expr(property)
+                    } else {
+                        Found::NotYet
+                    }
+                });
+                self.then(parts, || self.effect())
+            }
+            Expr::Call(CallExpr { callee, arguments })
+            | Expr::New(NewExpr { callee, arguments }) => {
+                let callee = self.expr(callee);
+                self.then(self.then(callee, || self.all(arguments)), || self.effect())
+            }
+            Expr::Sequence(exprs) => self.all(exprs),
+            Expr::Await(argument) => match self.expr(argument) {
+                Found::Yes => Found::Yes,
+                _ => Found::Blocked,
+            },
+            Expr::Yield(YieldExpr {
+                argument: Some(argument),
+                ..
+            }) => match self.expr(argument) {
+                Found::Yes => Found::Yes,
+                _ => Found::Blocked,
+            },
+            _ => Found::Blocked,
+        }
+    }
+}
@@ -2,6 +2,14 @@ use resast::expr::Expr;
 use resast::prelude::*;
 use std::fmt::Debug;
 
+pub mod blockify;
+pub mod inline;
+pub mod names;
+pub mod naming;
+pub mod purity;
+pub mod visit;
+pub mod visit_mut;
+
 // Any ResAST enum/struct that can be converted into a Block.
 #[derive(Debug)]
 pub enum Blockable<'a> {
@@ -0,0 +1,138 @@
+// Names a temp can't take, even when the program never mentions them.
+
+// Reserved words, plus names that are only reserved in strict mode or
+// modules, or that nothing should shadow.
+pub const RESERVED: &[&str] = &[
+    "arguments",
+    "await",
+    "break",
+    "case",
+    "catch",
+    "class",
+    "const",
+    "continue",
+    "debugger",
+    "default",
+    "delete",
+    "do",
+    "else",
+    "enum",
+    "eval",
+    "export",
+    "extends",
+    "false",
+    "finally",
+    "for",
+    "function",
+    "if",
+    "implements",
+    "import",
+    "in",
+    "instanceof",
+    "interface",
+    "let",
+    "new",
+    "null",
+    "package",
+    "private",
+    "protected",
+    "public",
+    "return",
+    "static",
+    "super",
+    "switch",
+    "this",
+    "throw",
+    "true",
+    "try",
+    "typeof",
+    "var",
+    "void",
+    "while",
+    "with",
+    "yield",
+    "undefined",
+    "NaN",
+    "Infinity",
+];
+
+// Browser globals that a script could read without declaring them. Shadowing
+// one of these only matters if the program uses it, but that can happen
+// through `eval` or a nested script, so they're never handed out.
+pub const GLOBALS: &[&str] = &[
+    "alert",
+    "atob",
+    "blur",
+    "btoa",
+    "caches",
+    "close",
+    "closed",
+    "confirm",
+    "console",
+    "crypto",
+    "document",
+    "escape",
+    "event",
+    "external",
+    "fetch",
+    "find",
+    "focus",
+    "frames",
+    "global",
+    "globalThis",
+    "history",
+    "indexedDB",
+    "innerHeight",
+    "innerWidth",
+    "isFinite",
+    "isNaN",
+    "length",
+    "localStorage",
+    "location",
+    "menubar",
+    "module",
+    "name",
+    "navigator",
+    "open",
+    "opener",
+    "origin",
+    "parent",
+    "parseFloat",
+    "parseInt",
+    "performance",
+    "print",
+    "process",
+    "prompt",
+    "require",
+    "screen",
+    "scroll",
+    "scrollBy",
+    "scrollTo",
+    "scrollX",
+    "scrollY",
+    "self",
+    "sessionStorage",
+    "status",
+    "stop",
+    "toolbar",
+    "top",
+    "unescape",
+    "window",
+];
+
+// Whether `name` can be written as a plain identifier.
+pub fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(first) if first.is_ascii_alphabetic() || first == '_' || first == '$' => {
+            chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$')
+        }
+        _ => false,
+    }
+}
+
+// Whether `name` is one a temp can't take, either a reserved word or a
+// global.
+pub fn is_unavailable(name: &str) -> bool {
+    RESERVED.contains(&name) || GLOBALS.contains(&name)
+}
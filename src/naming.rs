@@ -0,0 +1,213 @@
+use resast::expr::Expr;
+use resast::prelude::*;
+use std::fmt::Debug;
+
+// The word list that ships with the binary, so it runs from any directory.
+pub const WORDS: &str = include_str!("../wordlist_1.txt");
+
+// A source of temp names. The Blockifier asks for a name with the value the
+// temp will hold (when there is one), the code it's made for and what it's
+// for, like "first" for a loop's first-iteration flag, where the code is the
+// loop's test. `context` is the value itself when there is one. When a
+// proposal is already taken or isn't a usable identifier, it asks again with
+// `attempt` counted up, so a strategy must eventually propose something new.
+pub trait Namer {
+    fn propose(
+        &mut self,
+        value: Option<&Expr>,
+        context: &dyn Debug,
+        purpose: &str,
+        attempt: usize,
+    ) -> String;
+}
+
+// `_t1`, `_t2`, ... in the order temps are made.
+#[derive(Default)]
+pub struct Sequential {
+    count: usize,
+}
+
+impl Namer for Sequential {
+    fn propose(
+        &mut self,
+        _value: Option<&Expr>,
+        _context: &dyn Debug,
+        _purpose: &str,
+        _attempt: usize,
+    ) -> String {
+        self.count += 1;
+        format!("_t{}", self.count)
+    }
+}
+
+// Words from a list, one per line, then `_t1`, `_t2`, ... once it runs out.
+pub struct WordList<'w> {
+    words: std::str::Lines<'w>,
+    fallback: Sequential,
+}
+
+impl<'w> WordList<'w> {
+    pub fn new(words: &'w str) -> WordList<'w> {
+        WordList {
+            words: words.lines(),
+            fallback: Sequential::default(),
+        }
+    }
+}
+
+impl<'w> Namer for WordList<'w> {
+    fn propose(
+        &mut self,
+        value: Option<&Expr>,
+        context: &dyn Debug,
+        purpose: &str,
+        attempt: usize,
+    ) -> String {
+        match self.words.next() {
+            Some(word) => word.trim().to_string(),
+            None => self.fallback.propose(value, context, purpose, attempt),
+        }
+    }
+}
+
+// A hash of the value the temp holds, so the same expression gets the same
+// name no matter what else changed in the script. A temp without a value is
+// hashed from what it's for and the code it's made for, rather than from how
+// many came before it. Only temps for the same code get a counted suffix.
+pub struct Hashed;
+
+impl Namer for Hashed {
+    fn propose(
+        &mut self,
+        value: Option<&Expr>,
+        context: &dyn Debug,
+        purpose: &str,
+        attempt: usize,
+    ) -> String {
+        let content = match value {
+            Some(value) => format!("{:?}", value),
+            None => format!("{} {:?}", purpose, context),
+        };
+        // FNV-1a, which unlike the std hasher is the same on every build.
+        let hash = content
+            .bytes()
+            .fold(0xcbf2_9ce4_8422_2325u64, |hash, byte| {
+                (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+            });
+        let name = format!("_h{:06x}", hash & 0xff_ffff);
+        with_attempt(name, attempt)
+    }
+}
+
+// A name read off the value: `hiddenFlag` for `document.hidden == 0`,
+// `result_fetch` for `fetch(url)`. Falls back to the temp's purpose.
+pub struct Semantic;
+
+impl Namer for Semantic {
+    fn propose(
+        &mut self,
+        value: Option<&Expr>,
+        _context: &dyn Debug,
+        purpose: &str,
+        attempt: usize,
+    ) -> String {
+        let name = value
+            .and_then(describe)
+            .map(|name| identifier(&name))
+            .unwrap_or_else(|| purpose.to_string());
+        with_attempt(name, attempt)
+    }
+}
+
+fn with_attempt(name: String, attempt: usize) -> String {
+    if attempt == 0 {
+        name
+    } else {
+        format!("{}{}", name, attempt + 1)
+    }
+}
+
+// Keeps the characters an identifier can have, and makes sure it doesn't
+// start with a digit.
+fn identifier(name: &str) -> String {
+    let name: String = name
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric() || *c == '_' || *c == '$')
+        .collect();
+    match name.chars().next() {
+        Some(first) if !first.is_ascii_digit() => name,
+        _ => format!("_{}", name),
+    }
+}
+
+// The name a reader would use for the thing an expression refers to.
+fn base(expr: &Expr) -> Option<String> {
+    match expr {
+        Expr::Ident(Ident { name }) => Some(name.to_string()),
+        Expr::Member(MemberExpr {
+            property,
+            computed: false,
+            ..
+        }) => base(property),
+        Expr::Member(MemberExpr { property, .. }) => match &**property {
+            Expr::Lit(Lit::String(StringLit::Double(key)))
+            | Expr::Lit(Lit::String(StringLit::Single(key))) => Some(key.to_string()),
+            _ => None,
+        },
+        Expr::Call(CallExpr { callee, .. }) => base(callee),
+        _ => None,
+    }
+    .filter(|name| !name.is_empty())
+}
+
+fn describe(expr: &Expr) -> Option<String> {
+    match expr {
+        Expr::Ident(Ident { name }) => Some(format!("old_{}", name)),
+        Expr::Member(_) => base(expr),
+        Expr::Call(CallExpr { callee, .. }) => base(callee).map(|name| format!("result_{}", name)),
+        Expr::New(NewExpr { callee, .. }) => base(callee).map(|name| format!("new_{}", name)),
+        Expr::Binary(BinaryExpr {
+            operator,
+            left,
+            right,
+        }) => match operator {
+            BinaryOp::Equal
+            | BinaryOp::NotEqual
+            | BinaryOp::StrictEqual
+            | BinaryOp::StrictNotEqual
+            | BinaryOp::LessThan
+            | BinaryOp::GreaterThan
+            | BinaryOp::LessThanEqual
+            | BinaryOp::GreaterThanEqual
+            | BinaryOp::In
+            | BinaryOp::InstanceOf => base(left)
+                .or_else(|| base(right))
+                .map(|name| format!("{}Flag", name)),
+            _ => base(left).map(|name| format!("{}_calc", name)),
+        },
+        Expr::Unary(UnaryExpr {
+            operator: UnaryOp::Not,
+            argument,
+            ..
+        }) => base(argument).map(|name| format!("{}Flag", name)),
+        Expr::Unary(UnaryExpr {
+            operator: UnaryOp::TypeOf,
+            argument,
+            ..
+        }) => base(argument).map(|name| format!("{}Type", name)),
+        Expr::Logical(LogicalExpr { left, .. }) => describe(left),
+        Expr::Await(argument) => describe(argument),
+        Expr::Spread(argument) => base(argument).map(|name| format!("{}_items", name)),
+        Expr::Assign(AssignExpr {
+            left: AssignLeft::Expr(left),
+            ..
+        }) => base(left),
+        Expr::Array(_) => Some("list".to_string()),
+        Expr::Obj(_) => Some("object".to_string()),
+        Expr::Func(_) | Expr::ArrowFunc(_) => Some("callback".to_string()),
+        Expr::Lit(Lit::String(_)) | Expr::Lit(Lit::Template(_)) => Some("text".to_string()),
+        Expr::Lit(Lit::Number(_)) => Some("number".to_string()),
+        Expr::Conditional(_) => Some("choice".to_string()),
+        _ => None,
+    }
+}
@@ -0,0 +1,880 @@
+use crate::visit::{
+    walk_expr, walk_part, walk_pat, walk_program, walk_stmt, Bindings, Declared, Visit,
+};
+use resast::expr::Expr;
+use resast::prelude::*;
+use std::collections::{HashMap, HashSet};
+
+// What evaluating an expression can do, from least to most. An expression
+// does at least as much as any of its operands, so the effect of a compound
+// expression is the max over its parts and its own operation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Effect {
+    // Nothing at all: literals, functions, `this`.
+    Pure,
+    // Reads variables, so its value can change, but it can't throw.
+    ReadsOnly,
+    // Changes nothing, but can throw, like reading a global that might not
+    // exist or a property of `null`.
+    MayThrow,
+    // Anything else, including code that could run behind a getter or a
+    // `valueOf`.
+    SideEffecting,
+}
+
+// What the analysis may take for granted. Everything is off by default, which
+// is the safe choice for code that could be doing anything.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Assumptions {
+    // Reading a property never runs a getter, and converting a value to a
+    // number or string never runs `valueOf` or `toString`. Reading a property
+    // of `null` or `undefined` can still throw.
+    pub pure_getters: bool,
+    // Variables the program never declares are globals that exist, so
+    // reading one can't throw a ReferenceError.
+    pub globals_exist: bool,
+}
+
+pub struct Purity {
+    pub assumptions: Assumptions,
+    // Every name the program declares, in any scope.
+    declared: HashSet<String>,
+    // What `Resolver` found: names read somewhere they might not be bound,
+    // and names read somewhere they might not be initialized yet.
+    unbound: HashSet<String>,
+    uninitialized: HashSet<String>,
+}
+
+impl Purity {
+    pub fn new(program: &Program, assumptions: Assumptions) -> Purity {
+        let mut declared = Declared::default();
+        walk_program(&mut declared, program);
+        let mut resolver = Resolver {
+            declared: &declared.names,
+            assumptions,
+            scopes: vec![],
+            position: 1,
+            with: 0,
+            unbound: HashSet::new(),
+            uninitialized: HashSet::new(),
+        };
+        resolver.program(program);
+        Purity {
+            assumptions,
+            unbound: resolver.unbound,
+            uninitialized: resolver.uninitialized,
+            declared: declared.names,
+        }
+    }
+    // For names added after the program was read, like temps.
+    pub fn declare(&mut self, name: &str) {
+        self.declared.insert(name.to_string());
+    }
+    // Whether an expression whose value goes unused can be left out without
+    // anything changing.
+    pub fn is_dead(&self, expr: &Expr) -> bool {
+        self.effect(expr) <= Effect::ReadsOnly
+    }
+    pub fn effect(&self, expr: &Expr) -> Effect {
+        self.under(self.assumptions).effect(expr)
+    }
+    // Whether `expr` does anything on purpose, like calling a function or
+    // assigning, rather than just reading values. Getters and throws don't
+    // count, so `a.b && !a.c` has none.
+    pub fn has_side_effects(&self, expr: &Expr) -> bool {
+        let assumptions = Assumptions {
+            pure_getters: true,
+            ..self.assumptions
+        };
+        self.under(assumptions).effect(expr) == Effect::SideEffecting
+    }
+    // Whether evaluating `expr` could run code the program wrote, through a
+    // call, `new`, a tagged template, a getter or a `valueOf`. Assigning to a
+    // variable doesn't count, and neither does throwing.
+    pub fn runs_code(&self, expr: &Expr) -> bool {
+        let analysis = Analysis {
+            variable_writes: true,
+            ..self.under(self.assumptions)
+        };
+        analysis.effect(expr) == Effect::SideEffecting
+    }
+    fn under(&self, assumptions: Assumptions) -> Analysis<'_> {
+        Analysis {
+            assumptions,
+            purity: self,
+            variable_writes: false,
+        }
+    }
+}
+
+// The analysis itself, under a given set of assumptions.
+struct Analysis<'p> {
+    assumptions: Assumptions,
+    purity: &'p Purity,
+    // Assigning to or updating a variable only costs what its operands do,
+    // rather than counting as a side effect.
+    variable_writes: bool,
+}
+
+impl<'p> Analysis<'p> {
+    fn effect(&self, expr: &Expr) -> Effect {
+        match expr {
+            Expr::Lit(Lit::Template(TemplateLit { expressions, .. })) => {
+                self.converted(expressions.iter())
+            }
+            Expr::Lit(_) | Expr::This | Expr::Func(_) | Expr::ArrowFunc(_) => Effect::Pure,
+            Expr::Ident(Ident { name }) => self.read(name),
+            Expr::Array(elems) => self.all(elems.iter().flatten()),
+            Expr::Obj(props) => props
+                .iter()
+                .map(|prop| match prop {
+                    ObjProp::Prop(Prop {
+                        key,
+                        value,
+                        computed,
+                        ..
+                    }) => {
+                        let key = match key {
+                            PropKey::Expr(key) if *computed => self.converted(std::iter::once(key)),
+                            PropKey::Pat(_) if *computed => Effect::SideEffecting,
+                            // `{a}`
+                            PropKey::Pat(Pat::Ident(Ident { name })) => self.read(name),
+                            _ => Effect::Pure,
+                        };
+                        let value = match value {
+                            PropValue::Expr(value) => self.effect(value),
+                            PropValue::Pat(_) => Effect::SideEffecting,
+                            PropValue::None => Effect::Pure,
+                        };
+                        key.max(value)
+                    }
+                    // Copies run the source's getters.
+                    ObjProp::Spread(expr) => self.effect(expr).max(self.getter()),
+                })
+                .max()
+                .unwrap_or(Effect::Pure),
+            Expr::Unary(UnaryExpr {
+                operator, argument, ..
+            }) => match operator {
+                // `typeof` is the one read of a missing global that doesn't throw.
+                // It still throws before a `let` is initialized.
+                UnaryOp::TypeOf => match &**argument {
+                    Expr::Ident(Ident { name })
+                        if self.purity.uninitialized.contains(&name[..]) =>
+                    {
+                        Effect::MayThrow
+                    }
+                    Expr::Ident(_) => Effect::ReadsOnly,
+                    argument => self.effect(argument),
+                },
+                UnaryOp::Not | UnaryOp::Void => self.effect(argument),
+                UnaryOp::Delete => Effect::SideEffecting,
+                UnaryOp::Minus | UnaryOp::Plus | UnaryOp::Tilde => {
+                    self.converted(std::iter::once(&**argument))
+                }
+            },
+            Expr::Binary(BinaryExpr {
+                operator,
+                left,
+                right,
+            }) => match operator {
+                BinaryOp::StrictEqual | BinaryOp::StrictNotEqual => {
+                    self.all(vec![&**left, &**right])
+                }
+                // These can run user code (proxies, `Symbol.hasInstance`) and
+                // throw when the right side isn't an object.
+                BinaryOp::In | BinaryOp::InstanceOf => Effect::SideEffecting,
+                // Arithmetic, comparisons and `==` convert their operands.
+                // Mixing a BigInt with a number throws.
+                _ => {
+                    let operands = self.converted(vec![&**left, &**right]);
+                    if is_bigint(left) || is_bigint(right) {
+                        operands.max(Effect::MayThrow)
+                    } else {
+                        operands
+                    }
+                }
+            },
+            Expr::Logical(LogicalExpr { left, right, .. }) => self.all(vec![&**left, &**right]),
+            Expr::Conditional(ConditionalExpr {
+                test,
+                consequent,
+                alternate,
+            }) => self.all(vec![&**test, &**consequent, &**alternate]),
+            Expr::Member(MemberExpr {
+                object,
+                property,
+                computed,
+            }) => {
+                let mut effect = self.effect(object);
+                if *computed {
+                    effect = effect.max(self.converted(std::iter::once(&**property)));
+                }
+                let access = match &**object {
+                    // Never `null` or `undefined`.
+                    Expr::Array(_) | Expr::Obj(_) | Expr::Func(_) | Expr::ArrowFunc(_) => {
+                        self.getter()
+                    }
+                    Expr::Lit(lit) if *lit != Lit::Null => self.getter(),
+                    _ => self.getter().max(Effect::MayThrow),
+                };
+                effect.max(access)
+            }
+            Expr::Sequence(exprs) => self.all(exprs),
+            Expr::Assign(AssignExpr {
+                operator,
+                left: AssignLeft::Expr(left),
+                right,
+            }) if self.variable_writes && matches!(**left, Expr::Ident(_)) => match operator {
+                AssignOp::Equal => self.effect(right),
+                _ => self.converted(vec![&**left, &**right]),
+            },
+            Expr::Update(UpdateExpr { argument, .. })
+                if self.variable_writes && matches!(**argument, Expr::Ident(_)) =>
+            {
+                self.converted(std::iter::once(&**argument))
+            }
+            Expr::Class(Class {
+                super_class: None,
+                body: ClassBody(props),
+                ..
+            }) if props.iter().all(|prop| !prop.computed) => Effect::Pure,
+            _ => Effect::SideEffecting,
+        }
+    }
+    fn all<'e, 'a: 'e, I>(&self, exprs: I) -> Effect
+    where
+        I: IntoIterator<Item = &'e Expr<'a>>,
+    {
+        exprs
+            .into_iter()
+            .map(|expr| self.effect(expr))
+            .max()
+            .unwrap_or(Effect::Pure)
+    }
+    // Reading a variable throws unless every read of it is sure to find it
+    // bound and initialized.
+    fn read(&self, name: &str) -> Effect {
+        let purity = self.purity;
+        if purity.unbound.contains(name) || purity.uninitialized.contains(name) {
+            Effect::MayThrow
+        } else if purity.declared.contains(name) || is_global(name, self.assumptions) {
+            Effect::ReadsOnly
+        } else {
+            Effect::MayThrow
+        }
+    }
+    // What reading a property of an object adds.
+    fn getter(&self) -> Effect {
+        if self.assumptions.pure_getters {
+            Effect::ReadsOnly
+        } else {
+            Effect::SideEffecting
+        }
+    }
+    // Evaluating `exprs` and converting each value to a primitive, which can
+    // call the value's `valueOf` or `toString`.
+    fn converted<'e, 'a: 'e, I>(&self, exprs: I) -> Effect
+    where
+        I: IntoIterator<Item = &'e Expr<'a>>,
+    {
+        exprs
+            .into_iter()
+            .map(|expr| {
+                let effect = self.effect(expr);
+                if is_primitive(expr) {
+                    effect
+                } else {
+                    // Symbols can't be converted to numbers, even by a pure getter.
+                    effect.max(self.getter()).max(Effect::MayThrow)
+                }
+            })
+            .max()
+            .unwrap_or(Effect::Pure)
+    }
+}
+
+// Whether an expression always gives a primitive that converts without
+// running any code.
+fn is_primitive(expr: &Expr) -> bool {
+    match expr {
+        Expr::Lit(_) | Expr::Unary(_) | Expr::Binary(_) | Expr::Update(_) => true,
+        Expr::Ident(Ident { name }) => ["undefined", "NaN", "Infinity"].contains(&&name[..]),
+        Expr::Logical(LogicalExpr { left, right, .. }) => is_primitive(left) && is_primitive(right),
+        Expr::Conditional(ConditionalExpr {
+            consequent,
+            alternate,
+            ..
+        }) => is_primitive(consequent) && is_primitive(alternate),
+        Expr::Sequence(exprs) => exprs.last().is_some_and(is_primitive),
+        _ => false,
+    }
+}
+
+fn is_bigint(expr: &Expr) -> bool {
+    matches!(expr, Expr::Lit(Lit::Number(number)) if number.ends_with('n'))
+}
+
+// Globals that are always there.
+const BUILTINS: [&str; 4] = ["undefined", "NaN", "Infinity", "arguments"];
+
+// Whether a name the program doesn't declare can be read without throwing.
+fn is_global(name: &str, assumptions: Assumptions) -> bool {
+    assumptions.globals_exist || BUILTINS.contains(&name)
+}
+
+// When a binding can be read without throwing.
+#[derive(Clone, Copy)]
+enum Init {
+    // From this position on.
+    Ready(usize),
+    // Once its declaration runs, which it hasn't yet.
+    Pending,
+    // Who knows, like a `let` directly in a `switch` case.
+    Unknown,
+}
+
+struct Scope {
+    names: HashMap<String, Init>,
+    // Where the scope was entered, which is where its function declarations
+    // are created.
+    entered: usize,
+    // For the outermost scope of a function, where the function was created.
+    // Code inside only runs after that, so that's where its reads of outer
+    // variables count as happening.
+    created: Option<usize>,
+}
+
+// Resolves every read in the program against the scopes around it, in
+// source order. A read is fine when it finds a `var`, function, parameter or
+// import, or a `let`, `const` or class that's initialized before it. A read
+// that finds nothing is of a global, which might not exist.
+struct Resolver<'d> {
+    declared: &'d HashSet<String>,
+    assumptions: Assumptions,
+    scopes: Vec<Scope>,
+    // Counts up through the program, one step per read, per function created
+    // and per binding initialized.
+    position: usize,
+    // How many `with` statements are around, where any name could be a
+    // property of the object instead.
+    with: usize,
+    unbound: HashSet<String>,
+    uninitialized: HashSet<String>,
+}
+
+impl<'d> Resolver<'d> {
+    fn tick(&mut self) -> usize {
+        self.position += 1;
+        self.position
+    }
+    fn push(&mut self, created: Option<usize>) {
+        self.scopes.push(Scope {
+            names: HashMap::new(),
+            entered: self.position,
+            created,
+        });
+    }
+    fn bind(&mut self, name: &str, init: Init) {
+        let scope = self.scopes.last_mut().expect("Bound outside of a scope");
+        scope.names.insert(name.to_string(), init);
+    }
+    fn bind_pat(&mut self, pat: &Pat, init: Init) {
+        let mut bindings = Bindings::default();
+        bindings.visit_pat(pat);
+        for name in bindings.names {
+            self.bind(&name, init);
+        }
+    }
+    // Marks the nearest binding of `name` ready from here on.
+    fn ready(&mut self, name: &str) {
+        let position = self.tick();
+        let scope = self
+            .scopes
+            .iter_mut()
+            .rev()
+            .find(|scope| scope.names.contains_key(name));
+        if let Some(init) = scope.and_then(|scope| scope.names.get_mut(name)) {
+            if let Init::Pending = init {
+                *init = Init::Ready(position);
+            }
+        }
+    }
+    fn ready_pat(&mut self, pat: &Pat) {
+        let mut bindings = Bindings::default();
+        bindings.visit_pat(pat);
+        for name in bindings.names {
+            self.ready(&name);
+        }
+    }
+    fn program(&mut self, program: &Program) {
+        let parts = match program {
+            Program::Script(parts) => parts,
+            Program::Mod(parts) => parts,
+        };
+        self.body(parts);
+    }
+    // The body of a function or program: its `var`s are ready throughout,
+    // and its top level is a block.
+    fn body(&mut self, parts: &[ProgramPart]) {
+        self.push(None);
+        for name in Declared::vars(parts).names {
+            self.bind(&name, Init::Ready(0));
+        }
+        self.block(parts, Init::Pending);
+        self.scopes.pop();
+    }
+    // Declares what the top level of `parts` declares in the current scope,
+    // then visits them.
+    fn block(&mut self, parts: &[ProgramPart], lexical: Init) {
+        self.declare(parts, lexical);
+        for part in parts {
+            self.visit_part(part);
+        }
+    }
+    // Functions are ready throughout, and `let`s, `const`s and classes start
+    // out as `lexical`.
+    fn declare(&mut self, parts: &[ProgramPart], lexical: Init) {
+        for part in parts {
+            match part {
+                ProgramPart::Decl(Decl::Var(VarKind::Let | VarKind::Const, decls)) => {
+                    for decl in decls {
+                        self.bind_pat(&decl.id, lexical);
+                    }
+                }
+                ProgramPart::Decl(Decl::Class(Class { id: Some(id), .. })) => {
+                    self.bind(&id.name, lexical)
+                }
+                ProgramPart::Decl(Decl::Func(Func { id: Some(id), .. })) => {
+                    self.bind(&id.name, Init::Ready(0))
+                }
+                ProgramPart::Decl(Decl::Import(_)) => {
+                    let mut imports = Declared::default();
+                    imports.visit_part(part);
+                    for name in imports.names {
+                        self.bind(&name, Init::Ready(0));
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+    fn scoped(&mut self, BlockStmt(parts): &BlockStmt) {
+        self.push(None);
+        self.block(parts, Init::Pending);
+        self.scopes.pop();
+    }
+    // Visits what a binding pattern reads: its defaults and computed keys.
+    fn pat_reads(&mut self, pat: &Pat) {
+        struct Reads<'r, 'd> {
+            resolver: &'r mut Resolver<'d>,
+        }
+        impl<'a, 'r, 'd> Visit<'a> for Reads<'r, 'd> {
+            fn visit_expr(&mut self, expr: &Expr<'a>) {
+                self.resolver.visit_expr(expr)
+            }
+            fn visit_pat(&mut self, pat: &Pat<'a>) {
+                match pat {
+                    Pat::Ident(_) => {}
+                    Pat::Obj(parts) => {
+                        for part in parts {
+                            match part {
+                                ObjPatPart::Assign(Prop {
+                                    key,
+                                    value,
+                                    computed,
+                                    ..
+                                }) => {
+                                    match key {
+                                        PropKey::Expr(key) if *computed => self.visit_expr(key),
+                                        PropKey::Pat(key) => self.visit_pat(key),
+                                        _ => {}
+                                    }
+                                    match value {
+                                        PropValue::Expr(value) => self.visit_expr(value),
+                                        PropValue::Pat(value) => self.visit_pat(value),
+                                        PropValue::None => {}
+                                    }
+                                }
+                                ObjPatPart::Rest(pat) => self.visit_pat(pat),
+                            }
+                        }
+                    }
+                    pat => walk_pat(self, pat),
+                }
+            }
+        }
+        Reads { resolver: self }.visit_pat(pat)
+    }
+    fn decls(&mut self, kind: &VarKind, decls: &[VarDecl]) {
+        for VarDecl { id, init } in decls {
+            if let Some(init) = init {
+                self.visit_expr(init);
+            }
+            self.pat_reads(id);
+            if *kind != VarKind::Var {
+                self.ready_pat(id);
+            }
+        }
+    }
+    // Parameters are ready one at a time, so a default can't read the ones
+    // after it.
+    fn function(
+        &mut self,
+        id: Option<&Ident>,
+        params: &[FuncArg],
+        body: Result<&[ProgramPart], &Expr>,
+        created: usize,
+    ) {
+        self.push(Some(created));
+        if let Some(id) = id {
+            self.bind(&id.name, Init::Ready(0));
+        }
+        self.push(None);
+        let mut declared = Declared::default();
+        declared.bind_params(params);
+        for name in &declared.names {
+            self.bind(name, Init::Pending);
+        }
+        for param in params {
+            match param {
+                FuncArg::Pat(pat) => {
+                    self.pat_reads(pat);
+                    self.ready_pat(pat);
+                }
+                FuncArg::Expr(Expr::Ident(Ident { name })) => self.ready(name),
+                FuncArg::Expr(Expr::Assign(AssignExpr { left, right, .. })) => {
+                    self.visit_expr(right);
+                    match left {
+                        AssignLeft::Pat(pat) => {
+                            self.pat_reads(pat);
+                            self.ready_pat(pat);
+                        }
+                        AssignLeft::Expr(left) => match &**left {
+                            Expr::Ident(Ident { name }) => self.ready(name),
+                            left => self.visit_expr(left),
+                        },
+                    }
+                }
+                FuncArg::Expr(expr) => self.visit_expr(expr),
+            }
+        }
+        match body {
+            Ok(parts) => self.body(parts),
+            Err(expr) => self.visit_expr(expr),
+        }
+        self.scopes.pop();
+        self.scopes.pop();
+    }
+    // A class's heritage and computed keys run before its name is ready, and
+    // its methods can only run after.
+    fn class(&mut self, class: &Class, declared: bool) {
+        self.push(None);
+        if let Some(Ident { name }) = &class.id {
+            self.bind(name, Init::Pending);
+        }
+        if let Some(super_class) = &class.super_class {
+            self.visit_expr(super_class);
+        }
+        let ClassBody(props) = &class.body;
+        for prop in props.iter().filter(|prop| prop.computed) {
+            match &prop.key {
+                PropKey::Expr(key) => self.visit_expr(key),
+                PropKey::Pat(key) => self.visit_pat(key),
+                PropKey::Lit(_) => {}
+            }
+        }
+        if let Some(Ident { name }) = &class.id {
+            self.ready(name);
+            if declared {
+                let inner = self.scopes.pop().expect("Pushed above");
+                self.ready(name);
+                self.scopes.push(inner);
+            }
+        }
+        for prop in props {
+            if let PropValue::Expr(value) = &prop.value {
+                self.visit_expr(value);
+            }
+        }
+        self.scopes.pop();
+    }
+    fn decl(&mut self, decl: &Decl) {
+        match decl {
+            Decl::Var(kind, decls) => self.decls(kind, decls),
+            // Created at the top of its scope.
+            Decl::Func(Func { params, body, .. }) => {
+                let created = self.scopes.last().map_or(0, |scope| scope.entered);
+                let FuncBody(parts) = body;
+                self.function(None, params, Ok(parts), created);
+            }
+            Decl::Class(class) => self.class(class, true),
+            Decl::Import(_) => {}
+            Decl::Export(export) => match &**export {
+                ModExport::Default(DefaultExportDecl::Decl(decl))
+                | ModExport::Named(NamedExportDecl::Decl(decl)) => self.decl(decl),
+                ModExport::Default(DefaultExportDecl::Expr(expr)) => self.visit_expr(expr),
+                ModExport::Named(NamedExportDecl::Specifier(specs, _)) => {
+                    for spec in specs {
+                        self.visit_ident(&spec.local);
+                    }
+                }
+                ModExport::All(_) => {}
+            },
+        }
+    }
+}
+
+impl<'a, 'd> Visit<'a> for Resolver<'d> {
+    fn visit_part(&mut self, part: &ProgramPart<'a>) {
+        match part {
+            ProgramPart::Decl(decl) => self.decl(decl),
+            part => walk_part(self, part),
+        }
+    }
+    fn visit_stmt(&mut self, stmt: &Stmt<'a>) {
+        match stmt {
+            Stmt::Var(decls) => self.decls(&VarKind::Var, decls),
+            Stmt::Block(block) => self.scoped(block),
+            Stmt::With(WithStmt { object, body }) => {
+                self.visit_expr(object);
+                self.with += 1;
+                self.visit_stmt(body);
+                self.with -= 1;
+            }
+            // Any case can be jumped to, so there's no telling whether a
+            // `let` in an earlier one has run.
+            Stmt::Switch(SwitchStmt {
+                discriminant,
+                cases,
+            }) => {
+                self.visit_expr(discriminant);
+                self.push(None);
+                for SwitchCase { consequent, .. } in cases {
+                    self.declare(consequent, Init::Unknown);
+                }
+                for SwitchCase { test, consequent } in cases {
+                    if let Some(test) = test {
+                        self.visit_expr(test);
+                    }
+                    for part in consequent {
+                        self.visit_part(part);
+                    }
+                }
+                self.scopes.pop();
+            }
+            Stmt::Try(TryStmt {
+                block,
+                handler,
+                finalizer,
+            }) => {
+                self.scoped(block);
+                if let Some(CatchClause { param, body }) = handler {
+                    self.push(None);
+                    if let Some(param) = param {
+                        self.bind_pat(param, Init::Pending);
+                        self.pat_reads(param);
+                        self.ready_pat(param);
+                    }
+                    self.scoped(body);
+                    self.scopes.pop();
+                }
+                if let Some(finalizer) = finalizer {
+                    self.scoped(finalizer);
+                }
+            }
+            Stmt::For(ForStmt {
+                init: Some(LoopInit::Variable(kind, decls)),
+                test,
+                update,
+                body,
+            }) => {
+                self.push(None);
+                if *kind != VarKind::Var {
+                    for decl in decls {
+                        self.bind_pat(&decl.id, Init::Pending);
+                    }
+                }
+                self.decls(kind, decls);
+                for expr in test.iter().chain(update) {
+                    self.visit_expr(expr);
+                }
+                self.visit_stmt(body);
+                self.scopes.pop();
+            }
+            // The right side runs before the loop's own bindings are ready.
+            Stmt::ForIn(ForInStmt {
+                left: LoopLeft::Variable(kind, decl),
+                right,
+                body,
+            })
+            | Stmt::ForOf(ForOfStmt {
+                left: LoopLeft::Variable(kind, decl),
+                right,
+                body,
+                ..
+            }) => {
+                self.push(None);
+                if *kind != VarKind::Var {
+                    self.bind_pat(&decl.id, Init::Pending);
+                }
+                self.visit_expr(right);
+                self.decls(kind, std::slice::from_ref(decl));
+                self.visit_stmt(body);
+                self.scopes.pop();
+            }
+            stmt => walk_stmt(self, stmt),
+        }
+    }
+    fn visit_expr(&mut self, expr: &Expr<'a>) {
+        match expr {
+            Expr::ArrowFunc(ArrowFuncExpr { params, body, .. }) => {
+                let created = self.tick();
+                let body = match body {
+                    ArrowFuncBody::FuncBody(FuncBody(parts)) => Ok(&parts[..]),
+                    ArrowFuncBody::Expr(expr) => Err(&**expr),
+                };
+                self.function(None, params, body, created);
+            }
+            Expr::Class(class) => self.class(class, false),
+            expr => walk_expr(self, expr),
+        }
+    }
+    fn visit_func(&mut self, func: &Func<'a>) {
+        let created = self.tick();
+        let FuncBody(parts) = &func.body;
+        self.function(func.id.as_ref(), &func.params, Ok(parts), created);
+    }
+    fn visit_ident(&mut self, ident: &Ident<'a>) {
+        let name = &ident.name[..];
+        let mut at = self.tick();
+        let mut init = None;
+        for scope in self.scopes.iter().rev() {
+            if let Some(found) = scope.names.get(name) {
+                init = Some(*found);
+                break;
+            }
+            if let Some(created) = scope.created {
+                at = created;
+            }
+        }
+        match init {
+            _ if self.with > 0 => {
+                self.unbound.insert(name.to_string());
+            }
+            Some(Init::Ready(ready)) if ready < at => {}
+            Some(_) => {
+                self.uninitialized.insert(name.to_string());
+            }
+            None if BUILTINS.contains(&name) => {}
+            None if self.assumptions.globals_exist && !self.declared.contains(name) => {}
+            None => {
+                self.unbound.insert(name.to_string());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ressa::Parser;
+
+    const SAFE: Assumptions = Assumptions {
+        pure_getters: false,
+        globals_exist: false,
+    };
+    const PURE_GETTERS: Assumptions = Assumptions {
+        pure_getters: true,
+        globals_exist: false,
+    };
+
+    // Runs `check` on the analysis of `code` and its first expression
+    // statement.
+    fn first<T>(code: &str, assumptions: Assumptions, check: impl Fn(&Purity, &Expr) -> T) -> T {
+        let mut parser = Parser::new(code).expect("Failed to start parsing");
+        let program = parser.parse().expect("Failed to parse");
+        let purity = Purity::new(&program, assumptions);
+        let (Program::Script(parts) | Program::Mod(parts)) = &program;
+        let expr = parts
+            .iter()
+            .find_map(|part| match part {
+                ProgramPart::Stmt(Stmt::Expr(expr)) => Some(expr),
+                _ => None,
+            })
+            .expect("No expression statement");
+        check(&purity, expr)
+    }
+
+    fn effect(code: &str, assumptions: Assumptions) -> Effect {
+        first(code, assumptions, |purity, expr| purity.effect(expr))
+    }
+
+    #[test]
+    fn literals_and_functions_are_pure() {
+        assert_eq!(effect("1;", SAFE), Effect::Pure);
+        assert_eq!(effect("(function () { f(); });", SAFE), Effect::Pure);
+        assert_eq!(effect("`a${1}`;", SAFE), Effect::Pure);
+        assert_eq!(effect("1 + 2 * 3;", SAFE), Effect::Pure);
+    }
+
+    #[test]
+    fn variable_reads_throw_unless_declared_and_initialized() {
+        assert_eq!(effect("var a; a;", SAFE), Effect::ReadsOnly);
+        assert_eq!(effect("undefined;", SAFE), Effect::ReadsOnly);
+        assert_eq!(effect("a;", SAFE), Effect::MayThrow);
+        let globals = Assumptions {
+            globals_exist: true,
+            ..SAFE
+        };
+        assert_eq!(effect("a;", globals), Effect::ReadsOnly);
+        assert_eq!(effect("typeof a;", SAFE), Effect::ReadsOnly);
+        assert_eq!(effect("a; let a;", SAFE), Effect::MayThrow);
+        assert_eq!(effect("typeof a; let a;", SAFE), Effect::MayThrow);
+    }
+
+    #[test]
+    fn property_reads_run_getters_and_can_throw() {
+        assert_eq!(effect("var a; a.b;", SAFE), Effect::SideEffecting);
+        assert_eq!(effect("var a; a.b;", PURE_GETTERS), Effect::MayThrow);
+        assert_eq!(
+            effect("var a; [a].length;", PURE_GETTERS),
+            Effect::ReadsOnly
+        );
+        assert_eq!(effect("null.b;", PURE_GETTERS), Effect::MayThrow);
+    }
+
+    #[test]
+    fn conversions_run_code_and_can_throw() {
+        assert_eq!(effect("var a; a + 1;", SAFE), Effect::SideEffecting);
+        assert_eq!(effect("var a; a + 1;", PURE_GETTERS), Effect::MayThrow);
+        assert_eq!(effect("var a; a === 1;", SAFE), Effect::ReadsOnly);
+        assert_eq!(effect("1n + 1;", SAFE), Effect::MayThrow);
+        assert_eq!(effect("var a; ({[a]: 1});", PURE_GETTERS), Effect::MayThrow);
+    }
+
+    #[test]
+    fn calls_and_assignments_have_side_effects() {
+        assert_eq!(effect("f();", SAFE), Effect::SideEffecting);
+        assert_eq!(effect("var a; a = 1;", SAFE), Effect::SideEffecting);
+        assert_eq!(effect("var a; delete a.b;", SAFE), Effect::SideEffecting);
+    }
+
+    #[test]
+    fn reads_only_are_not_side_effects() {
+        let has_side_effects =
+            |code| first(code, SAFE, |purity, expr| purity.has_side_effects(expr));
+        assert!(!has_side_effects("var a; 0 < a.b && !a.c;"));
+        assert!(has_side_effects("var a; a.b && f();"));
+    }
+
+    #[test]
+    fn variable_writes_do_not_run_code() {
+        let runs_code = |code| first(code, SAFE, |purity, expr| purity.runs_code(expr));
+        assert!(!runs_code("var a; a = 1;"));
+        assert!(!runs_code("var a, b; a = b;"));
+        assert!(runs_code("var a; a++;"));
+        assert!(runs_code("var a; a.b = 1;"));
+        assert!(runs_code("var a; a += {};"));
+    }
+}
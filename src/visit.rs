@@ -0,0 +1,648 @@
+use resast::expr::Expr;
+use resast::prelude::*;
+use std::borrow::Cow;
+use std::collections::HashSet;
+
+// Read-only traversal of a ResAST tree. Override a method to look at a kind
+// of node, and call the matching `walk_*` function to keep descending.
+//
+// Non-computed member properties and property keys are not visited, since
+// they aren't variable references. Shorthand keys (`{a}`) are.
+pub trait Visit<'a> {
+    fn visit_part(&mut self, part: &ProgramPart<'a>) {
+        walk_part(self, part)
+    }
+    fn visit_stmt(&mut self, stmt: &Stmt<'a>) {
+        walk_stmt(self, stmt)
+    }
+    fn visit_expr(&mut self, expr: &Expr<'a>) {
+        walk_expr(self, expr)
+    }
+    fn visit_pat(&mut self, pat: &Pat<'a>) {
+        walk_pat(self, pat)
+    }
+    fn visit_func(&mut self, func: &Func<'a>) {
+        walk_func(self, func)
+    }
+    fn visit_ident(&mut self, _ident: &Ident<'a>) {}
+}
+
+pub fn walk_program<'a, V: Visit<'a> + ?Sized>(v: &mut V, prog: &Program<'a>) {
+    let parts = match prog {
+        Program::Script(parts) => parts,
+        Program::Mod(parts) => parts,
+    };
+    for part in parts {
+        v.visit_part(part);
+    }
+}
+
+pub fn walk_part<'a, V: Visit<'a> + ?Sized>(v: &mut V, part: &ProgramPart<'a>) {
+    match part {
+        ProgramPart::Stmt(stmt) => v.visit_stmt(stmt),
+        ProgramPart::Decl(decl) => walk_decl(v, decl),
+        ProgramPart::Dir(_) => {}
+    }
+}
+
+pub fn walk_decl<'a, V: Visit<'a> + ?Sized>(v: &mut V, decl: &Decl<'a>) {
+    match decl {
+        Decl::Var(_, decls) => walk_var_decls(v, decls),
+        Decl::Func(func) => v.visit_func(func),
+        Decl::Class(class) => walk_class(v, class),
+        Decl::Import(import) => {
+            for spec in &import.specifiers {
+                match spec {
+                    ImportSpecifier::Normal(NormalImportSpec { local, .. }) => v.visit_ident(local),
+                    ImportSpecifier::Default(local) => v.visit_ident(local),
+                    ImportSpecifier::Namespace(local) => v.visit_ident(local),
+                }
+            }
+        }
+        Decl::Export(export) => match &**export {
+            ModExport::Default(DefaultExportDecl::Decl(decl)) => walk_decl(v, decl),
+            ModExport::Default(DefaultExportDecl::Expr(expr)) => v.visit_expr(expr),
+            ModExport::Named(NamedExportDecl::Decl(decl)) => walk_decl(v, decl),
+            ModExport::Named(NamedExportDecl::Specifier(specs, _)) => {
+                for spec in specs {
+                    v.visit_ident(&spec.local);
+                }
+            }
+            ModExport::All(_) => {}
+        },
+    }
+}
+
+pub fn walk_var_decls<'a, V: Visit<'a> + ?Sized>(v: &mut V, decls: &[VarDecl<'a>]) {
+    for VarDecl { id, init } in decls {
+        v.visit_pat(id);
+        if let Some(init) = init {
+            v.visit_expr(init);
+        }
+    }
+}
+
+pub fn walk_stmt<'a, V: Visit<'a> + ?Sized>(v: &mut V, stmt: &Stmt<'a>) {
+    match stmt {
+        Stmt::Expr(expr) | Stmt::Throw(expr) => v.visit_expr(expr),
+        Stmt::Block(BlockStmt(parts)) => {
+            for part in parts {
+                v.visit_part(part);
+            }
+        }
+        Stmt::Empty | Stmt::Debugger | Stmt::Break(_) | Stmt::Continue(_) => {}
+        Stmt::With(WithStmt { object, body }) => {
+            v.visit_expr(object);
+            v.visit_stmt(body);
+        }
+        Stmt::Return(expr) => {
+            if let Some(expr) = expr {
+                v.visit_expr(expr);
+            }
+        }
+        Stmt::Labeled(LabeledStmt { body, .. }) => v.visit_stmt(body),
+        Stmt::If(IfStmt {
+            test,
+            consequent,
+            alternate,
+        }) => {
+            v.visit_expr(test);
+            v.visit_stmt(consequent);
+            if let Some(alternate) = alternate {
+                v.visit_stmt(alternate);
+            }
+        }
+        Stmt::Switch(SwitchStmt {
+            discriminant,
+            cases,
+        }) => {
+            v.visit_expr(discriminant);
+            for SwitchCase { test, consequent } in cases {
+                if let Some(test) = test {
+                    v.visit_expr(test);
+                }
+                for part in consequent {
+                    v.visit_part(part);
+                }
+            }
+        }
+        Stmt::Try(TryStmt {
+            block: BlockStmt(block),
+            handler,
+            finalizer,
+        }) => {
+            for part in block {
+                v.visit_part(part);
+            }
+            if let Some(CatchClause {
+                param,
+                body: BlockStmt(body),
+            }) = handler
+            {
+                if let Some(param) = param {
+                    v.visit_pat(param);
+                }
+                for part in body {
+                    v.visit_part(part);
+                }
+            }
+            if let Some(BlockStmt(finalizer)) = finalizer {
+                for part in finalizer {
+                    v.visit_part(part);
+                }
+            }
+        }
+        Stmt::While(WhileStmt { test, body }) | Stmt::DoWhile(DoWhileStmt { test, body }) => {
+            v.visit_expr(test);
+            v.visit_stmt(body);
+        }
+        Stmt::For(ForStmt {
+            init,
+            test,
+            update,
+            body,
+        }) => {
+            match init {
+                Some(LoopInit::Variable(_, decls)) => walk_var_decls(v, decls),
+                Some(LoopInit::Expr(expr)) => v.visit_expr(expr),
+                None => {}
+            }
+            for expr in test.iter().chain(update) {
+                v.visit_expr(expr);
+            }
+            v.visit_stmt(body);
+        }
+        Stmt::ForIn(ForInStmt { left, right, body })
+        | Stmt::ForOf(ForOfStmt {
+            left, right, body, ..
+        }) => {
+            match left {
+                LoopLeft::Expr(expr) => v.visit_expr(expr),
+                LoopLeft::Variable(_, decl) => walk_var_decls(v, std::slice::from_ref(decl)),
+                LoopLeft::Pat(pat) => v.visit_pat(pat),
+            }
+            v.visit_expr(right);
+            v.visit_stmt(body);
+        }
+        Stmt::Var(decls) => walk_var_decls(v, decls),
+    }
+}
+
+pub fn walk_func<'a, V: Visit<'a> + ?Sized>(v: &mut V, func: &Func<'a>) {
+    if let Some(id) = &func.id {
+        v.visit_ident(id);
+    }
+    walk_params(v, &func.params);
+    let FuncBody(parts) = &func.body;
+    for part in parts {
+        v.visit_part(part);
+    }
+}
+
+pub fn walk_params<'a, V: Visit<'a> + ?Sized>(v: &mut V, params: &[FuncArg<'a>]) {
+    for param in params {
+        match param {
+            FuncArg::Expr(expr) => v.visit_expr(expr),
+            FuncArg::Pat(pat) => v.visit_pat(pat),
+        }
+    }
+}
+
+pub fn walk_class<'a, V: Visit<'a> + ?Sized>(v: &mut V, class: &Class<'a>) {
+    if let Some(id) = &class.id {
+        v.visit_ident(id);
+    }
+    if let Some(super_class) = &class.super_class {
+        v.visit_expr(super_class);
+    }
+    let ClassBody(props) = &class.body;
+    for prop in props {
+        walk_prop(v, prop);
+    }
+}
+
+pub fn walk_prop<'a, V: Visit<'a> + ?Sized>(v: &mut V, prop: &Prop<'a>) {
+    if prop.computed || prop.short_hand {
+        match &prop.key {
+            PropKey::Expr(expr) => v.visit_expr(expr),
+            PropKey::Pat(pat) => v.visit_pat(pat),
+            PropKey::Lit(_) => {}
+        }
+    }
+    match &prop.value {
+        PropValue::Expr(expr) => v.visit_expr(expr),
+        PropValue::Pat(pat) => v.visit_pat(pat),
+        PropValue::None => {}
+    }
+}
+
+pub fn walk_template<'a, V: Visit<'a> + ?Sized>(v: &mut V, template: &TemplateLit<'a>) {
+    for expr in &template.expressions {
+        v.visit_expr(expr);
+    }
+}
+
+pub fn walk_expr<'a, V: Visit<'a> + ?Sized>(v: &mut V, expr: &Expr<'a>) {
+    match expr {
+        Expr::Array(elems) => {
+            for elem in elems.iter().flatten() {
+                v.visit_expr(elem);
+            }
+        }
+        Expr::ArrowFunc(ArrowFuncExpr { params, body, .. }) => {
+            walk_params(v, params);
+            match body {
+                ArrowFuncBody::FuncBody(FuncBody(parts)) => {
+                    for part in parts {
+                        v.visit_part(part);
+                    }
+                }
+                ArrowFuncBody::Expr(expr) => v.visit_expr(expr),
+            }
+        }
+        Expr::ArrowParamPlaceHolder(params, _) => walk_params(v, params),
+        Expr::Assign(AssignExpr { left, right, .. }) => {
+            match left {
+                AssignLeft::Pat(pat) => v.visit_pat(pat),
+                AssignLeft::Expr(expr) => v.visit_expr(expr),
+            }
+            v.visit_expr(right);
+        }
+        Expr::Await(expr) | Expr::Spread(expr) => v.visit_expr(expr),
+        Expr::Binary(BinaryExpr { left, right, .. })
+        | Expr::Logical(LogicalExpr { left, right, .. }) => {
+            v.visit_expr(left);
+            v.visit_expr(right);
+        }
+        Expr::Class(class) => walk_class(v, class),
+        Expr::Call(CallExpr { callee, arguments }) | Expr::New(NewExpr { callee, arguments }) => {
+            v.visit_expr(callee);
+            for arg in arguments {
+                v.visit_expr(arg);
+            }
+        }
+        Expr::Conditional(ConditionalExpr {
+            test,
+            consequent,
+            alternate,
+        }) => {
+            v.visit_expr(test);
+            v.visit_expr(consequent);
+            v.visit_expr(alternate);
+        }
+        Expr::Func(func) => v.visit_func(func),
+        Expr::Ident(ident) => v.visit_ident(ident),
+        Expr::Lit(Lit::Template(template)) => walk_template(v, template),
+        Expr::Lit(_) | Expr::MetaProp(_) | Expr::Super | Expr::This => {}
+        Expr::Member(MemberExpr {
+            object,
+            property,
+            computed,
+        }) => {
+            v.visit_expr(object);
+            if *computed {
+                v.visit_expr(property);
+            }
+        }
+        Expr::Obj(props) => {
+            for prop in props {
+                match prop {
+                    ObjProp::Prop(prop) => walk_prop(v, prop),
+                    ObjProp::Spread(expr) => v.visit_expr(expr),
+                }
+            }
+        }
+        Expr::Sequence(exprs) => {
+            for expr in exprs {
+                v.visit_expr(expr);
+            }
+        }
+        Expr::TaggedTemplate(TaggedTemplateExpr { tag, quasi }) => {
+            v.visit_expr(tag);
+            walk_template(v, quasi);
+        }
+        Expr::Unary(UnaryExpr { argument, .. }) | Expr::Update(UpdateExpr { argument, .. }) => {
+            v.visit_expr(argument)
+        }
+        Expr::Yield(YieldExpr { argument, .. }) => {
+            if let Some(argument) = argument {
+                v.visit_expr(argument);
+            }
+        }
+    }
+}
+
+pub fn walk_pat<'a, V: Visit<'a> + ?Sized>(v: &mut V, pat: &Pat<'a>) {
+    match pat {
+        Pat::Ident(ident) => v.visit_ident(ident),
+        Pat::Obj(parts) => {
+            for part in parts {
+                match part {
+                    ObjPatPart::Assign(prop) => walk_prop(v, prop),
+                    ObjPatPart::Rest(pat) => v.visit_pat(pat),
+                }
+            }
+        }
+        Pat::Array(parts) => {
+            for part in parts.iter().flatten() {
+                match part {
+                    ArrayPatPart::Pat(pat) => v.visit_pat(pat),
+                    ArrayPatPart::Expr(expr) => v.visit_expr(expr),
+                }
+            }
+        }
+        Pat::RestElement(pat) => v.visit_pat(pat),
+        Pat::Assign(AssignPat { left, right }) => {
+            v.visit_pat(left);
+            v.visit_expr(right);
+        }
+    }
+}
+
+// Checks whether a tree assigns to, updates or declares `name` anywhere,
+// including inside nested functions.
+pub struct WritesTo<'n> {
+    pub name: &'n str,
+    pub found: bool,
+}
+
+impl<'n> WritesTo<'n> {
+    pub fn new(name: &'n str) -> WritesTo<'n> {
+        WritesTo { name, found: false }
+    }
+    fn check(&mut self, expr: &Expr) {
+        if let Expr::Ident(Ident { name }) = expr {
+            self.found |= name == self.name;
+        }
+    }
+}
+
+impl<'a, 'n> Visit<'a> for WritesTo<'n> {
+    fn visit_stmt(&mut self, stmt: &Stmt<'a>) {
+        match stmt {
+            Stmt::ForIn(ForInStmt {
+                left: LoopLeft::Expr(left),
+                ..
+            })
+            | Stmt::ForOf(ForOfStmt {
+                left: LoopLeft::Expr(left),
+                ..
+            }) => self.check(left),
+            _ => {}
+        }
+        walk_stmt(self, stmt)
+    }
+    fn visit_expr(&mut self, expr: &Expr<'a>) {
+        match expr {
+            Expr::Assign(AssignExpr {
+                left: AssignLeft::Expr(left),
+                ..
+            }) => self.check(left),
+            Expr::Update(UpdateExpr { argument, .. }) => self.check(argument),
+            _ => {}
+        }
+        walk_expr(self, expr)
+    }
+    // Patterns only show up where something is bound or assigned.
+    fn visit_pat(&mut self, pat: &Pat<'a>) {
+        if let Pat::Ident(Ident { name }) = pat {
+            self.found |= name == self.name;
+        }
+        walk_pat(self, pat)
+    }
+    fn visit_func(&mut self, func: &Func<'a>) {
+        if let Some(Ident { name }) = &func.id {
+            self.found |= name == self.name;
+        }
+        walk_func(self, func)
+    }
+}
+
+// Checks whether a tree can pause at an `await` or `yield`, letting other code
+// run in between. Nested functions pause on their own, so they're skipped.
+#[derive(Default)]
+pub struct Suspends {
+    pub found: bool,
+}
+
+impl<'a> Visit<'a> for Suspends {
+    fn visit_expr(&mut self, expr: &Expr<'a>) {
+        match expr {
+            Expr::Await(_) | Expr::Yield(_) => self.found = true,
+            Expr::ArrowFunc(_) => return,
+            _ => {}
+        }
+        walk_expr(self, expr)
+    }
+    fn visit_stmt(&mut self, stmt: &Stmt<'a>) {
+        if let Stmt::ForOf(ForOfStmt { is_await: true, .. }) = stmt {
+            self.found = true;
+        }
+        walk_stmt(self, stmt)
+    }
+    fn visit_func(&mut self, _func: &Func<'a>) {}
+}
+
+// Collects the names a pattern binds. Default values and member targets are
+// skipped, so nested functions don't contribute their parameters.
+#[derive(Default)]
+pub struct Bindings<'a> {
+    pub names: Vec<Cow<'a, str>>,
+}
+
+impl<'a> Visit<'a> for Bindings<'a> {
+    fn visit_expr(&mut self, _expr: &Expr<'a>) {}
+    fn visit_pat(&mut self, pat: &Pat<'a>) {
+        match pat {
+            Pat::Ident(Ident { name }) => {
+                if !self.names.contains(name) {
+                    self.names.push(name.clone());
+                }
+            }
+            // Computed keys (`{[d]: x}`) are references, not bindings.
+            Pat::Obj(parts) => {
+                for part in parts {
+                    match part {
+                        ObjPatPart::Assign(Prop {
+                            key: PropKey::Pat(key),
+                            value: PropValue::None,
+                            ..
+                        }) => self.visit_pat(key),
+                        ObjPatPart::Assign(Prop {
+                            value: PropValue::Pat(value),
+                            ..
+                        }) => self.visit_pat(value),
+                        ObjPatPart::Assign(_) => {}
+                        ObjPatPart::Rest(pat) => self.visit_pat(pat),
+                    }
+                }
+            }
+            _ => walk_pat(self, pat),
+        }
+    }
+}
+
+// Collects every identifier in a tree, bound or free, in any scope.
+#[derive(Default)]
+pub struct Names {
+    pub names: HashSet<String>,
+}
+
+impl<'a> Visit<'a> for Names {
+    fn visit_ident(&mut self, ident: &Ident<'a>) {
+        if !self.names.contains(&ident.name[..]) {
+            self.names.insert(ident.name.to_string());
+        }
+    }
+}
+
+// Collects every name the program declares, in any scope: variables,
+// functions, classes, parameters, catch parameters and imports.
+#[derive(Default)]
+pub struct Declared {
+    pub names: HashSet<String>,
+    // Stops at nested functions, naming only them, for the names a function
+    // body declares itself.
+    shallow: bool,
+    // Only names `var`, skipping nested functions entirely.
+    vars: bool,
+}
+
+impl Declared {
+    pub fn shallow() -> Declared {
+        Declared {
+            shallow: true,
+            ..Declared::default()
+        }
+    }
+    // The names a function body declares for all of itself: its `var`s,
+    // wherever they are, and the functions, classes, `let`s and `const`s at
+    // its top level. Names that only a nested block declares are left out.
+    pub fn body(parts: &[ProgramPart]) -> Declared {
+        let mut declared = Declared::vars(parts);
+        for part in parts {
+            match part {
+                ProgramPart::Decl(Decl::Var(_, decls)) => declared.bind_decls(decls),
+                ProgramPart::Decl(Decl::Func(func)) => declared.bind_id(&func.id),
+                ProgramPart::Decl(Decl::Class(class)) => declared.bind_id(&class.id),
+                _ => {}
+            }
+        }
+        declared
+    }
+    // Just the `var`s of a function body, wherever they are.
+    pub fn vars(parts: &[ProgramPart]) -> Declared {
+        let mut declared = Declared {
+            shallow: true,
+            vars: true,
+            ..Declared::default()
+        };
+        for part in parts {
+            declared.visit_part(part);
+        }
+        declared
+    }
+    fn bind(&mut self, pat: &Pat) {
+        let mut bindings = Bindings::default();
+        bindings.visit_pat(pat);
+        self.names
+            .extend(bindings.names.iter().map(|name| name.to_string()));
+    }
+    fn bind_decls(&mut self, decls: &[VarDecl]) {
+        for decl in decls {
+            self.bind(&decl.id);
+        }
+    }
+    pub fn bind_params(&mut self, params: &[FuncArg]) {
+        for param in params {
+            match param {
+                FuncArg::Pat(pat) => self.bind(pat),
+                FuncArg::Expr(Expr::Ident(Ident { name })) => {
+                    self.names.insert(name.to_string());
+                }
+                FuncArg::Expr(Expr::Assign(AssignExpr {
+                    left: AssignLeft::Pat(pat),
+                    ..
+                })) => self.bind(pat),
+                FuncArg::Expr(_) => {}
+            }
+        }
+    }
+    fn bind_id(&mut self, id: &Option<Ident>) {
+        if let Some(Ident { name }) = id {
+            self.names.insert(name.to_string());
+        }
+    }
+}
+
+impl<'a> Visit<'a> for Declared {
+    fn visit_part(&mut self, part: &ProgramPart<'a>) {
+        match part {
+            ProgramPart::Decl(Decl::Var(VarKind::Var, decls)) if self.vars => {
+                self.bind_decls(decls)
+            }
+            ProgramPart::Decl(_) if self.vars => {}
+            ProgramPart::Decl(Decl::Var(_, decls)) => self.bind_decls(decls),
+            ProgramPart::Decl(Decl::Class(class)) => self.bind_id(&class.id),
+            ProgramPart::Decl(Decl::Import(import)) => {
+                for specifier in &import.specifiers {
+                    let local = match specifier {
+                        ImportSpecifier::Normal(NormalImportSpec { local, .. }) => local,
+                        ImportSpecifier::Default(local) | ImportSpecifier::Namespace(local) => {
+                            local
+                        }
+                    };
+                    self.names.insert(local.name.to_string());
+                }
+            }
+            _ => {}
+        }
+        walk_part(self, part)
+    }
+    fn visit_stmt(&mut self, stmt: &Stmt<'a>) {
+        match stmt {
+            Stmt::Var(decls) => self.bind_decls(decls),
+            Stmt::For(ForStmt {
+                init: Some(LoopInit::Variable(kind, decls)),
+                ..
+            }) if !self.vars || *kind == VarKind::Var => self.bind_decls(decls),
+            Stmt::ForIn(ForInStmt {
+                left: LoopLeft::Variable(kind, decl),
+                ..
+            })
+            | Stmt::ForOf(ForOfStmt {
+                left: LoopLeft::Variable(kind, decl),
+                ..
+            }) if !self.vars || *kind == VarKind::Var => self.bind(&decl.id),
+            Stmt::Try(_) if self.vars => {}
+            Stmt::Try(TryStmt {
+                handler:
+                    Some(CatchClause {
+                        param: Some(param), ..
+                    }),
+                ..
+            }) => self.bind(param),
+            _ => {}
+        }
+        walk_stmt(self, stmt)
+    }
+    fn visit_expr(&mut self, expr: &Expr<'a>) {
+        match expr {
+            Expr::ArrowFunc(_) if self.shallow => return,
+            Expr::ArrowFunc(ArrowFuncExpr { params, .. }) => self.bind_params(params),
+            Expr::Class(class) if !self.vars => self.bind_id(&class.id),
+            _ => {}
+        }
+        walk_expr(self, expr)
+    }
+    fn visit_func(&mut self, func: &Func<'a>) {
+        if self.vars {
+            return;
+        }
+        self.bind_id(&func.id);
+        if !self.shallow {
+            self.bind_params(&func.params);
+            walk_func(self, func)
+        }
+    }
+}
@@ -0,0 +1,325 @@
+use resast::expr::Expr;
+use resast::prelude::*;
+
+// The mutable twin of `visit::Visit`, for passes that rewrite the tree after
+// it's been blockified. Every list of statements (a program, block, function
+// body, switch case or try clause) goes through `visit_parts`, so a pass that
+// merges or drops statements only needs to override that.
+//
+// Like `Visit`, non-computed member properties and property keys are skipped.
+pub trait VisitMut<'a> {
+    fn visit_parts(&mut self, parts: &mut Vec<ProgramPart<'a>>) {
+        walk_parts(self, parts)
+    }
+    fn visit_part(&mut self, part: &mut ProgramPart<'a>) {
+        walk_part(self, part)
+    }
+    fn visit_stmt(&mut self, stmt: &mut Stmt<'a>) {
+        walk_stmt(self, stmt)
+    }
+    fn visit_expr(&mut self, expr: &mut Expr<'a>) {
+        walk_expr(self, expr)
+    }
+    fn visit_pat(&mut self, pat: &mut Pat<'a>) {
+        walk_pat(self, pat)
+    }
+    fn visit_func(&mut self, func: &mut Func<'a>) {
+        walk_func(self, func)
+    }
+}
+
+pub fn walk_program<'a, V: VisitMut<'a> + ?Sized>(v: &mut V, prog: &mut Program<'a>) {
+    match prog {
+        Program::Script(parts) | Program::Mod(parts) => v.visit_parts(parts),
+    }
+}
+
+pub fn walk_parts<'a, V: VisitMut<'a> + ?Sized>(v: &mut V, parts: &mut Vec<ProgramPart<'a>>) {
+    for part in parts {
+        v.visit_part(part);
+    }
+}
+
+pub fn walk_part<'a, V: VisitMut<'a> + ?Sized>(v: &mut V, part: &mut ProgramPart<'a>) {
+    match part {
+        ProgramPart::Stmt(stmt) => v.visit_stmt(stmt),
+        ProgramPart::Decl(decl) => walk_decl(v, decl),
+        ProgramPart::Dir(_) => {}
+    }
+}
+
+pub fn walk_decl<'a, V: VisitMut<'a> + ?Sized>(v: &mut V, decl: &mut Decl<'a>) {
+    match decl {
+        Decl::Var(_, decls) => walk_var_decls(v, decls),
+        Decl::Func(func) => v.visit_func(func),
+        Decl::Class(class) => walk_class(v, class),
+        Decl::Import(_) => {}
+        Decl::Export(export) => match &mut **export {
+            ModExport::Default(DefaultExportDecl::Decl(decl)) => walk_decl(v, decl),
+            ModExport::Default(DefaultExportDecl::Expr(expr)) => v.visit_expr(expr),
+            ModExport::Named(NamedExportDecl::Decl(decl)) => walk_decl(v, decl),
+            ModExport::Named(NamedExportDecl::Specifier(..)) | ModExport::All(_) => {}
+        },
+    }
+}
+
+pub fn walk_var_decls<'a, V: VisitMut<'a> + ?Sized>(v: &mut V, decls: &mut [VarDecl<'a>]) {
+    for VarDecl { id, init } in decls {
+        v.visit_pat(id);
+        if let Some(init) = init {
+            v.visit_expr(init);
+        }
+    }
+}
+
+pub fn walk_stmt<'a, V: VisitMut<'a> + ?Sized>(v: &mut V, stmt: &mut Stmt<'a>) {
+    match stmt {
+        Stmt::Expr(expr) | Stmt::Throw(expr) => v.visit_expr(expr),
+        Stmt::Block(BlockStmt(parts)) => v.visit_parts(parts),
+        Stmt::Empty | Stmt::Debugger | Stmt::Break(_) | Stmt::Continue(_) => {}
+        Stmt::With(WithStmt { object, body }) => {
+            v.visit_expr(object);
+            v.visit_stmt(body);
+        }
+        Stmt::Return(expr) => {
+            if let Some(expr) = expr {
+                v.visit_expr(expr);
+            }
+        }
+        Stmt::Labeled(LabeledStmt { body, .. }) => v.visit_stmt(body),
+        Stmt::If(IfStmt {
+            test,
+            consequent,
+            alternate,
+        }) => {
+            v.visit_expr(test);
+            v.visit_stmt(consequent);
+            if let Some(alternate) = alternate {
+                v.visit_stmt(alternate);
+            }
+        }
+        Stmt::Switch(SwitchStmt {
+            discriminant,
+            cases,
+        }) => {
+            v.visit_expr(discriminant);
+            for SwitchCase { test, consequent } in cases {
+                if let Some(test) = test {
+                    v.visit_expr(test);
+                }
+                v.visit_parts(consequent);
+            }
+        }
+        Stmt::Try(TryStmt {
+            block: BlockStmt(block),
+            handler,
+            finalizer,
+        }) => {
+            v.visit_parts(block);
+            if let Some(CatchClause {
+                param,
+                body: BlockStmt(body),
+            }) = handler
+            {
+                if let Some(param) = param {
+                    v.visit_pat(param);
+                }
+                v.visit_parts(body);
+            }
+            if let Some(BlockStmt(finalizer)) = finalizer {
+                v.visit_parts(finalizer);
+            }
+        }
+        Stmt::While(WhileStmt { test, body }) | Stmt::DoWhile(DoWhileStmt { test, body }) => {
+            v.visit_expr(test);
+            v.visit_stmt(body);
+        }
+        Stmt::For(ForStmt {
+            init,
+            test,
+            update,
+            body,
+        }) => {
+            match init {
+                Some(LoopInit::Variable(_, decls)) => walk_var_decls(v, decls),
+                Some(LoopInit::Expr(expr)) => v.visit_expr(expr),
+                None => {}
+            }
+            for expr in test.iter_mut().chain(update) {
+                v.visit_expr(expr);
+            }
+            v.visit_stmt(body);
+        }
+        Stmt::ForIn(ForInStmt { left, right, body })
+        | Stmt::ForOf(ForOfStmt {
+            left, right, body, ..
+        }) => {
+            match left {
+                LoopLeft::Expr(expr) => v.visit_expr(expr),
+                LoopLeft::Variable(_, decl) => walk_var_decls(v, std::slice::from_mut(decl)),
+                LoopLeft::Pat(pat) => v.visit_pat(pat),
+            }
+            v.visit_expr(right);
+            v.visit_stmt(body);
+        }
+        Stmt::Var(decls) => walk_var_decls(v, decls),
+    }
+}
+
+pub fn walk_func<'a, V: VisitMut<'a> + ?Sized>(v: &mut V, func: &mut Func<'a>) {
+    walk_params(v, &mut func.params);
+    let FuncBody(parts) = &mut func.body;
+    v.visit_parts(parts);
+}
+
+pub fn walk_params<'a, V: VisitMut<'a> + ?Sized>(v: &mut V, params: &mut [FuncArg<'a>]) {
+    for param in params {
+        match param {
+            FuncArg::Expr(expr) => v.visit_expr(expr),
+            FuncArg::Pat(pat) => v.visit_pat(pat),
+        }
+    }
+}
+
+pub fn walk_class<'a, V: VisitMut<'a> + ?Sized>(v: &mut V, class: &mut Class<'a>) {
+    if let Some(super_class) = &mut class.super_class {
+        v.visit_expr(super_class);
+    }
+    let ClassBody(props) = &mut class.body;
+    for prop in props {
+        walk_prop(v, prop);
+    }
+}
+
+pub fn walk_prop<'a, V: VisitMut<'a> + ?Sized>(v: &mut V, prop: &mut Prop<'a>) {
+    if prop.computed || prop.short_hand {
+        match &mut prop.key {
+            PropKey::Expr(expr) => v.visit_expr(expr),
+            PropKey::Pat(pat) => v.visit_pat(pat),
+            PropKey::Lit(_) => {}
+        }
+    }
+    match &mut prop.value {
+        PropValue::Expr(expr) => v.visit_expr(expr),
+        PropValue::Pat(pat) => v.visit_pat(pat),
+        PropValue::None => {}
+    }
+}
+
+pub fn walk_template<'a, V: VisitMut<'a> + ?Sized>(v: &mut V, template: &mut TemplateLit<'a>) {
+    for expr in &mut template.expressions {
+        v.visit_expr(expr);
+    }
+}
+
+pub fn walk_expr<'a, V: VisitMut<'a> + ?Sized>(v: &mut V, expr: &mut Expr<'a>) {
+    match expr {
+        Expr::Array(elems) => {
+            for elem in elems.iter_mut().flatten() {
+                v.visit_expr(elem);
+            }
+        }
+        Expr::ArrowFunc(ArrowFuncExpr { params, body, .. }) => {
+            walk_params(v, params);
+            match body {
+                ArrowFuncBody::FuncBody(FuncBody(parts)) => v.visit_parts(parts),
+                ArrowFuncBody::Expr(expr) => v.visit_expr(expr),
+            }
+        }
+        Expr::ArrowParamPlaceHolder(params, _) => walk_params(v, params),
+        Expr::Assign(AssignExpr { left, right, .. }) => {
+            match left {
+                AssignLeft::Pat(pat) => v.visit_pat(pat),
+                AssignLeft::Expr(expr) => v.visit_expr(expr),
+            }
+            v.visit_expr(right);
+        }
+        Expr::Await(expr) | Expr::Spread(expr) => v.visit_expr(expr),
+        Expr::Binary(BinaryExpr { left, right, .. })
+        | Expr::Logical(LogicalExpr { left, right, .. }) => {
+            v.visit_expr(left);
+            v.visit_expr(right);
+        }
+        Expr::Class(class) => walk_class(v, class),
+        Expr::Call(CallExpr { callee, arguments }) | Expr::New(NewExpr { callee, arguments }) => {
+            v.visit_expr(callee);
+            for arg in arguments {
+                v.visit_expr(arg);
+            }
+        }
+        Expr::Conditional(ConditionalExpr {
+            test,
+            consequent,
+            alternate,
+        }) => {
+            v.visit_expr(test);
+            v.visit_expr(consequent);
+            v.visit_expr(alternate);
+        }
+        Expr::Func(func) => v.visit_func(func),
+        Expr::Lit(Lit::Template(template)) => walk_template(v, template),
+        Expr::Ident(_) | Expr::Lit(_) | Expr::MetaProp(_) | Expr::Super | Expr::This => {}
+        Expr::Member(MemberExpr {
+            object,
+            property,
+            computed,
+        }) => {
+            v.visit_expr(object);
+            if *computed {
+                v.visit_expr(property);
+            }
+        }
+        Expr::Obj(props) => {
+            for prop in props {
+                match prop {
+                    ObjProp::Prop(prop) => walk_prop(v, prop),
+                    ObjProp::Spread(expr) => v.visit_expr(expr),
+                }
+            }
+        }
+        Expr::Sequence(exprs) => {
+            for expr in exprs {
+                v.visit_expr(expr);
+            }
+        }
+        Expr::TaggedTemplate(TaggedTemplateExpr { tag, quasi }) => {
+            v.visit_expr(tag);
+            walk_template(v, quasi);
+        }
+        Expr::Unary(UnaryExpr { argument, .. }) | Expr::Update(UpdateExpr { argument, .. }) => {
+            v.visit_expr(argument)
+        }
+        Expr::Yield(YieldExpr { argument, .. }) => {
+            if let Some(argument) = argument {
+                v.visit_expr(argument);
+            }
+        }
+    }
+}
+
+pub fn walk_pat<'a, V: VisitMut<'a> + ?Sized>(v: &mut V, pat: &mut Pat<'a>) {
+    match pat {
+        Pat::Ident(_) => {}
+        Pat::Obj(parts) => {
+            for part in parts {
+                match part {
+                    ObjPatPart::Assign(prop) => walk_prop(v, prop),
+                    ObjPatPart::Rest(pat) => v.visit_pat(pat),
+                }
+            }
+        }
+        Pat::Array(parts) => {
+            for part in parts.iter_mut().flatten() {
+                match part {
+                    ArrayPatPart::Pat(pat) => v.visit_pat(pat),
+                    ArrayPatPart::Expr(expr) => v.visit_expr(expr),
+                }
+            }
+        }
+        Pat::RestElement(pat) => v.visit_pat(pat),
+        Pat::Assign(AssignPat { left, right }) => {
+            v.visit_pat(left);
+            v.visit_expr(right);
+        }
+    }
+}
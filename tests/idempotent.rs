@@ -0,0 +1,75 @@
+// Checks that deblockifying is a no-op on its own output, for every script in
+// `scripts/`, like `scripts/idempotent.sh` does through the binary.
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::thread;
+
+use r_deob::blockify::{deblockify, Level, Options, Temps};
+use r_deob::naming::{Namer, WordList, WORDS};
+
+// Deblockifying recurses deeper than a test thread's stack goes, like in the
+// binary.
+const STACK_SIZE: usize = 64 << 20;
+
+fn scripts() -> Vec<PathBuf> {
+    let scripts = Path::new(env!("CARGO_MANIFEST_DIR")).join("scripts");
+    let mut scripts: Vec<PathBuf> = fs::read_dir(scripts)
+        .expect("No scripts directory")
+        .map(|entry| entry.expect("Couldn't list scripts").path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "js"))
+        .collect();
+    scripts.sort();
+    scripts
+}
+
+fn run(code: &str, options: Options) -> String {
+    let make_namer = || -> Box<dyn Namer> { Box::new(WordList::new(WORDS)) };
+    deblockify(code, options, make_namer)
+        .unwrap_or_else(|error| panic!("{}", error))
+        .code
+}
+
+fn check_scripts() {
+    let configs = [
+        ("default", Options::default()),
+        (
+            "--level=control",
+            Options {
+                lowerings: Level::Control.lowerings(),
+                ..Options::default()
+            },
+        ),
+        (
+            "--temps=var",
+            Options {
+                temps: Temps::Var,
+                ..Options::default()
+            },
+        ),
+    ];
+    let mut failures = vec![];
+    for script in scripts() {
+        let code = fs::read_to_string(&script).expect("Failed to read");
+        for (flags, options) in configs {
+            let once = run(&code, options);
+            if run(&once, options) != once {
+                failures.push(format!("{} {}", script.display(), flags));
+            }
+        }
+    }
+    assert!(
+        failures.is_empty(),
+        "Changed on a second run:\n{}",
+        failures.join("\n")
+    );
+}
+
+#[test]
+fn deblockifying_twice_changes_nothing() {
+    thread::Builder::new()
+        .stack_size(STACK_SIZE)
+        .spawn(check_scripts)
+        .expect("Couldn't start thread")
+        .join()
+        .expect("Checking the scripts panicked");
+}
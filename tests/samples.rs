@@ -0,0 +1,97 @@
+// Runs every `scripts/*_test.js` sample through deblockify with a few sets of
+// options, and checks that node prints the same for the output as for the
+// sample. Skipped when node isn't installed.
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::thread;
+
+use r_deob::blockify::{deblockify, Level, Options, Temps};
+use r_deob::naming::{Namer, WordList, WORDS};
+
+// Deblockifying recurses deeper than a test thread's stack goes, like in the
+// binary.
+const STACK_SIZE: usize = 64 << 20;
+
+fn samples() -> Vec<PathBuf> {
+    let scripts = Path::new(env!("CARGO_MANIFEST_DIR")).join("scripts");
+    let mut samples: Vec<PathBuf> = fs::read_dir(scripts)
+        .expect("No scripts directory")
+        .map(|entry| entry.expect("Couldn't list scripts").path())
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.ends_with("_test.js"))
+        })
+        .collect();
+    samples.sort();
+    samples
+}
+
+// What node prints for a script, errors included.
+fn node(script: &Path) -> Option<String> {
+    let output = Command::new("node").arg(script).output().ok()?;
+    let mut printed = String::from_utf8_lossy(&output.stdout).into_owned();
+    printed.push_str(&String::from_utf8_lossy(&output.stderr));
+    Some(printed)
+}
+
+fn configs() -> Vec<(&'static str, Options)> {
+    let level = |level: Level| Options {
+        lowerings: level.lowerings(),
+        ..Options::default()
+    };
+    vec![
+        ("default", Options::default()),
+        ("--level=sequences", level(Level::Sequences)),
+        ("--level=control", level(Level::Control)),
+        (
+            "--temps=var --split-assignments",
+            Options {
+                temps: Temps::Var,
+                split_assignments: true,
+                ..Options::default()
+            },
+        ),
+    ]
+}
+
+#[test]
+fn samples_print_the_same() {
+    if Command::new("node").arg("-v").output().is_err() {
+        eprintln!("node isn't installed, skipping");
+        return;
+    }
+    thread::Builder::new()
+        .stack_size(STACK_SIZE)
+        .spawn(check_samples)
+        .expect("Couldn't start thread")
+        .join()
+        .expect("Checking the samples panicked");
+}
+
+fn check_samples() {
+    let work = Path::new(env!("CARGO_TARGET_TMPDIR")).join("samples");
+    fs::create_dir_all(&work).expect("Couldn't make a work directory");
+
+    let mut failures = vec![];
+    for sample in samples() {
+        let code = fs::read_to_string(&sample).expect("Failed to read");
+        let expected = node(&sample).expect("node stopped working");
+        for (flags, options) in configs() {
+            let make_namer = || -> Box<dyn Namer> { Box::new(WordList::new(WORDS)) };
+            let output = deblockify(&code, options, make_namer)
+                .unwrap_or_else(|error| panic!("{} {}: {}", sample.display(), flags, error));
+            let out = work.join(sample.file_name().expect("Sample has a name"));
+            fs::write(&out, &output.code).expect("Failed to write");
+            if node(&out).as_ref() != Some(&expected) {
+                failures.push(format!("{} {}", sample.display(), flags));
+            }
+        }
+    }
+    assert!(
+        failures.is_empty(),
+        "Printed something else:\n{}",
+        failures.join("\n")
+    );
+}